@@ -0,0 +1,9 @@
+CREATE TABLE block_submitter_progress (
+    id SMALLINT PRIMARY KEY DEFAULT 1 CHECK (id = 1),
+    last_fetched_block_id BIGINT NOT NULL DEFAULT -1,
+    last_sent_block_id BIGINT NOT NULL DEFAULT -1,
+    last_confirmed_block_id BIGINT NOT NULL DEFAULT -1,
+    updated_time TIMESTAMP(0) NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO block_submitter_progress (id) VALUES (1);
@@ -0,0 +1,18 @@
+CREATE TYPE l1_tx_status AS ENUM('pending', 'mined', 'dropped');
+
+CREATE TABLE l1_pending_tx (
+    id SERIAL PRIMARY KEY,
+    block_id BIGINT NOT NULL,
+    nonce BIGINT NOT NULL,
+    tx_hash VARCHAR(66) NOT NULL UNIQUE,
+    gas_limit BIGINT NOT NULL,
+    gas_price BIGINT,
+    max_fee_per_gas BIGINT,
+    max_priority_fee_per_gas BIGINT,
+    raw_tx BYTEA NOT NULL,
+    status l1_tx_status NOT NULL DEFAULT 'pending',
+    created_time TIMESTAMP(0) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time TIMESTAMP(0) NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX l1_pending_tx_idx_status ON l1_pending_tx (status);
@@ -0,0 +1,5 @@
+ALTER TYPE l1_tx_status ADD VALUE 'replaced';
+
+ALTER TABLE l1_pending_tx ADD COLUMN tx_request JSONB;
+
+CREATE INDEX l1_pending_tx_idx_nonce ON l1_pending_tx (nonce);
@@ -0,0 +1,2 @@
+-- a batched `submitBlocks` transaction covers `block_count` consecutive blocks starting at `block_id`
+ALTER TABLE l1_pending_tx ADD COLUMN block_count INT NOT NULL DEFAULT 1;
@@ -0,0 +1,7 @@
+CREATE TABLE block_submission (
+    block_id BIGINT PRIMARY KEY,
+    revert_reason TEXT,
+    reverted_time TIMESTAMP(0),
+    created_time TIMESTAMP(0) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time TIMESTAMP(0) NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
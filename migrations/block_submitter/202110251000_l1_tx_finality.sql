@@ -0,0 +1,5 @@
+ALTER TYPE l1_tx_status ADD VALUE 'finalized';
+ALTER TYPE l1_tx_status ADD VALUE 'reorged';
+
+ALTER TABLE l1_pending_tx ADD COLUMN l1_block_number BIGINT;
+ALTER TABLE l1_pending_tx ADD COLUMN l1_block_hash VARCHAR(66);
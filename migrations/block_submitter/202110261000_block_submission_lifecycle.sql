@@ -0,0 +1,18 @@
+CREATE TYPE submission_status AS ENUM('uncommitted', 'submitting', 'committed', 'verified', 'finalized');
+
+ALTER TABLE block_submission ADD COLUMN status submission_status NOT NULL DEFAULT 'uncommitted';
+ALTER TABLE block_submission ADD COLUMN submitting_time TIMESTAMP(0);
+ALTER TABLE block_submission ADD COLUMN committed_time TIMESTAMP(0);
+ALTER TABLE block_submission ADD COLUMN verified_time TIMESTAMP(0);
+ALTER TABLE block_submission ADD COLUMN finalized_time TIMESTAMP(0);
+
+CREATE INDEX block_submission_idx_status ON block_submission (status);
+
+-- how long each block spent in each stage, NULL for stages it has not left yet
+CREATE VIEW block_submission_stage_duration AS
+SELECT block_id,
+       status,
+       committed_time - submitting_time AS submitting_duration,
+       verified_time - committed_time   AS committed_duration,
+       finalized_time - verified_time   AS verified_duration
+FROM block_submission;
@@ -0,0 +1,2 @@
+-- last L1 block whose contract events have been synced into `l2_block`
+ALTER TABLE block_submitter_progress ADD COLUMN last_synced_l1_block BIGINT;
@@ -0,0 +1,11 @@
+CREATE TABLE operator_alert (
+    id SERIAL PRIMARY KEY,
+    kind VARCHAR(32) NOT NULL,
+    detail TEXT NOT NULL,
+    raised_time TIMESTAMP(0) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time TIMESTAMP(0) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    cleared_time TIMESTAMP(0)
+);
+
+-- at most one open alert per kind
+CREATE UNIQUE INDEX operator_alert_idx_open ON operator_alert (kind) WHERE cleared_time IS NULL;
@@ -0,0 +1,2 @@
+ALTER TABLE block_submission ADD COLUMN rejected_reason TEXT;
+ALTER TABLE block_submission ADD COLUMN rejected_time TIMESTAMP(0);
@@ -0,0 +1,35 @@
+-- what a submitter in shadow mode would have submitted, it never broadcasts nor touches `l2_block`
+CREATE TABLE block_submission_shadow (
+    block_id BIGINT PRIMARY KEY,
+    -- the batch the block would have gone in
+    batch_first_block_id BIGINT NOT NULL,
+    batch_block_count INT NOT NULL,
+    tx_request JSONB,
+    gas_limit BIGINT,
+    revert_reason TEXT,
+    guard_breach TEXT,
+    rejected_reason TEXT,
+    created_time TIMESTAMP(0) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time TIMESTAMP(0) NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE block_submitter_shadow_progress (
+    id SMALLINT PRIMARY KEY DEFAULT 1 CHECK (id = 1),
+    last_fetched_block_id BIGINT NOT NULL DEFAULT -1,
+    updated_time TIMESTAMP(0) NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO block_submitter_shadow_progress (id) VALUES (1);
+
+-- shadow outcome of each block next to what the live submitter did
+CREATE VIEW block_submission_shadow_diff AS
+SELECT s.block_id,
+       s.revert_reason   AS shadow_revert_reason,
+       s.guard_breach    AS shadow_guard_breach,
+       s.rejected_reason AS shadow_rejected_reason,
+       s.gas_limit       AS shadow_gas_limit,
+       b.status          AS live_status,
+       b.revert_reason   AS live_revert_reason,
+       b.rejected_reason AS live_rejected_reason
+FROM block_submission_shadow s
+         LEFT JOIN block_submission b ON b.block_id = s.block_id;
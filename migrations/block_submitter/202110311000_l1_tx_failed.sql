@@ -0,0 +1,2 @@
+-- mined, but reverted
+ALTER TYPE l1_tx_status ADD VALUE 'failed';
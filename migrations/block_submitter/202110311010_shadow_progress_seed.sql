@@ -0,0 +1,5 @@
+-- unset until the first shadow run seeds it from where the live submitter or the chain got to,
+-- replaying blocks that are on-chain already would only record reverts
+ALTER TABLE block_submitter_shadow_progress ALTER COLUMN last_fetched_block_id DROP NOT NULL;
+ALTER TABLE block_submitter_shadow_progress ALTER COLUMN last_fetched_block_id DROP DEFAULT;
+UPDATE block_submitter_shadow_progress SET last_fetched_block_id = NULL WHERE last_fetched_block_id = -1;
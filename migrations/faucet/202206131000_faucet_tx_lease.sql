@@ -0,0 +1,9 @@
+ALTER TYPE tx_status ADD VALUE 'failed';
+
+-- a claim is a lease: a task claimed longer than the lease timeout ago is claimed again
+ALTER TABLE faucet_tx ADD COLUMN claimed_at TIMESTAMP(0);
+ALTER TABLE faucet_tx ADD COLUMN attempts INT NOT NULL DEFAULT 0;
+ALTER TABLE faucet_tx ADD COLUMN last_error TEXT;
+ALTER TABLE faucet_tx ADD COLUMN next_attempt_at TIMESTAMP(0) NOT NULL DEFAULT CURRENT_TIMESTAMP;
+
+CREATE INDEX faucet_tx_idx_status ON faucet_tx (status, next_attempt_at);
@@ -0,0 +1,6 @@
+-- a user is funded at most once per asset and campaign, redelivered registrations are no-ops.
+-- rows proposed before campaigns existed keep a null campaign, so their history is left as it is
+-- and the duplicates among them do not hold up the unique index (nulls never conflict)
+ALTER TABLE faucet_tx ADD COLUMN campaign VARCHAR(64);
+ALTER TABLE faucet_tx ALTER COLUMN campaign SET DEFAULT 'default';
+CREATE UNIQUE INDEX faucet_tx_idx_user_asset_campaign ON faucet_tx (to_user, asset, campaign);
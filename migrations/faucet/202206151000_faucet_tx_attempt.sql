@@ -0,0 +1,13 @@
+-- every gRPC call funding a faucet_tx, with what the matchengine answered
+CREATE TABLE faucet_tx_attempt (
+    id SERIAL PRIMARY KEY,
+    faucet_tx_id INT NOT NULL REFERENCES faucet_tx(id),
+    attempt INT NOT NULL,
+    error_code VARCHAR(30),
+    error_message TEXT,
+    response TEXT,
+    started_time TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    finished_time TIMESTAMP(3)
+);
+
+CREATE INDEX faucet_tx_attempt_idx_tx ON faucet_tx_attempt (faucet_tx_id, attempt);
@@ -0,0 +1,9 @@
+CREATE TYPE attempt_outcome AS ENUM('succeeded', 'duplicate', 'failed');
+
+-- the matchengine's answer as columns rather than a debug string: how it went, the gRPC status
+-- code, and the balance update it was about, to look it up in the matchengine's history
+ALTER TABLE faucet_tx_attempt DROP COLUMN response;
+ALTER TABLE faucet_tx_attempt ADD COLUMN outcome attempt_outcome;
+ALTER TABLE faucet_tx_attempt ADD COLUMN grpc_code INT;
+ALTER TABLE faucet_tx_attempt ADD COLUMN business VARCHAR(64);
+ALTER TABLE faucet_tx_attempt ADD COLUMN business_id BIGINT;
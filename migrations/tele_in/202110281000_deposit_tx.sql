@@ -0,0 +1,36 @@
+CREATE TABLE tele_in_user (
+    user_id INT CHECK (user_id >= 0) PRIMARY KEY,
+    l1_address VARCHAR(42) NOT NULL,
+    l2_pubkey VARCHAR(66) NOT NULL UNIQUE,
+    created_time TIMESTAMP(0) NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TYPE deposit_status AS ENUM('observed', 'credited', 'failed');
+
+CREATE TABLE deposit_tx (
+    id SERIAL PRIMARY KEY,
+    l1_tx_hash VARCHAR(66) NOT NULL,
+    l1_log_index BIGINT NOT NULL,
+    l1_block_number BIGINT NOT NULL,
+    -- derived from (l1_tx_hash, l1_log_index), replays are deduplicated by the matchengine
+    business_id BIGINT NOT NULL UNIQUE,
+    token_id INT NOT NULL,
+    to_pubkey VARCHAR(66) NOT NULL,
+    asset VARCHAR(30),
+    amount DECIMAL(30, 8),
+    status deposit_status NOT NULL DEFAULT 'observed',
+    last_error TEXT,
+    created_time TIMESTAMP(0) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time TIMESTAMP(0) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (l1_tx_hash, l1_log_index)
+);
+
+CREATE INDEX deposit_tx_idx_status ON deposit_tx (status);
+
+CREATE TABLE tele_in_progress (
+    id SMALLINT PRIMARY KEY DEFAULT 1 CHECK (id = 1),
+    last_synced_l1_block BIGINT,
+    updated_time TIMESTAMP(0) NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO tele_in_progress (id) VALUES (1);
@@ -0,0 +1,5 @@
+-- keep the full token precision, an 18-decimal deposit was rounded to 8 decimals, possibly up
+ALTER TABLE deposit_tx ALTER COLUMN amount TYPE NUMERIC;
+
+-- failed gRPC credits, the deposit is marked failed after the configured number of them
+ALTER TABLE deposit_tx ADD COLUMN attempts INT NOT NULL DEFAULT 0;
@@ -0,0 +1,5 @@
+-- deposits that can never be credited, e.g. of an unknown or out of range token id
+ALTER TYPE deposit_status ADD VALUE 'rejected';
+
+-- a token id beyond INT is kept in last_error only
+ALTER TABLE deposit_tx ALTER COLUMN token_id DROP NOT NULL;
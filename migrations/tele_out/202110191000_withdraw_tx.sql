@@ -0,0 +1,26 @@
+CREATE TABLE tele_out_user (
+    user_id INT CHECK (user_id >= 0) PRIMARY KEY,
+    l1_address VARCHAR(42) NOT NULL,
+    l2_pubkey VARCHAR(66) NOT NULL,
+    created_time TIMESTAMP(0) NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TYPE withdraw_status AS ENUM('received', 'paying', 'paid', 'confirmed', 'failed');
+
+CREATE TABLE withdraw_tx (
+    id SERIAL PRIMARY KEY,
+    mq_partition INT NOT NULL,
+    mq_offset BIGINT NOT NULL,
+    from_user INT CHECK (from_user >= 0) NOT NULL,
+    asset VARCHAR(30) NOT NULL,
+    amount DECIMAL(30, 8) NOT NULL,
+    l1_address VARCHAR(42),
+    status withdraw_status NOT NULL DEFAULT 'received',
+    l1_tx_hash VARCHAR(66),
+    last_error TEXT,
+    created_time TIMESTAMP(0) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_time TIMESTAMP(0) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (mq_partition, mq_offset)
+);
+
+CREATE INDEX withdraw_tx_idx_status ON withdraw_tx (status);
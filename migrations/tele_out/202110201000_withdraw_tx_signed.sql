@@ -0,0 +1,2 @@
+ALTER TABLE withdraw_tx ADD COLUMN nonce BIGINT;
+ALTER TABLE withdraw_tx ADD COLUMN raw_tx BYTEA;
@@ -0,0 +1,2 @@
+-- keep the full precision of assets with more than 8 decimals
+ALTER TABLE withdraw_tx ALTER COLUMN amount TYPE DECIMAL;
@@ -1,6 +1,6 @@
 use fluidex_common::non_blocking_tracing;
 use futures::{channel::mpsc, executor::block_on, SinkExt, StreamExt};
-use regnbue_bridge::block_submitter::{storage, EthSender, Settings, TaskFetcher};
+use regnbue_bridge::block_submitter::{storage, EthSender, EventListener, Reconciler, ReorgWatcher, Settings, TaskFetcher};
 use std::cell::RefCell;
 
 #[tokio::main]
@@ -28,11 +28,41 @@ async fn main() -> anyhow::Result<()> {
 
     // TODO: maybe separate and have: 1. consumer 2. producer 3. sender
     let dbpool = storage::from_config(&settings).await?;
-    let (tx, rx) = crossbeam_channel::unbounded();
+    let (tx, rx) = tokio::sync::mpsc::channel(settings.batch.queue_size.max(1));
+
+    if settings.shadow {
+        // only the pipeline up to signing, the other actors write `l2_block`
+        log::warn!("Block Submitter running in shadow mode");
+        let eth_sender = EthSender::from_config_with_pool(&settings, dbpool.clone()).await?;
+        let mut fetcher = TaskFetcher::from_config_with_pool(&settings, dbpool);
+        let fetcher_task_handle = tokio::spawn(async move { fetcher.run(tx).await });
+        let eth_sender_task_handle = tokio::spawn(async move { eth_sender.run(rx).await });
+
+        tokio::select! {
+            _ = async { fetcher_task_handle.await } => {
+                panic!("Block Submitter task fetcher actor is not supposed to finish its execution")
+            },
+            _ = async { eth_sender_task_handle.await } => {
+                panic!("Ethereum Sender actor is not supposed to finish its execution")
+            },
+            _ = async { stop_signal_receiver.next().await } => {
+                log::warn!("Stop signal received, shutting down");
+            }
+        };
+        return Ok(());
+    }
+
+    let reconciler = Reconciler::from_config_with_pool(&settings, dbpool.clone()).await?;
+    reconciler.startup().await?;
+    let eth_sender = EthSender::from_config_with_pool(&settings, dbpool.clone()).await?;
     let mut fetcher = TaskFetcher::from_config_with_pool(&settings, dbpool.clone());
     let fetcher_task_handle = tokio::spawn(async move { fetcher.run(tx).await });
-    let eth_sender = EthSender::from_config_with_pool(&settings, dbpool).await?;
     let eth_sender_task_handle = tokio::spawn(async move { eth_sender.run(rx).await });
+    let reorg_watcher = ReorgWatcher::from_config_with_pool(&settings, dbpool.clone()).await?;
+    let reorg_watcher_task_handle = tokio::spawn(async move { reorg_watcher.run().await });
+    let reconciler_task_handle = tokio::spawn(async move { reconciler.run().await });
+    let event_listener = EventListener::from_config_with_pool(&settings, dbpool).await?;
+    let event_listener_task_handle = tokio::spawn(async move { event_listener.run().await });
 
     tokio::select! {
         _ = async { fetcher_task_handle.await } => {
@@ -41,6 +71,16 @@ async fn main() -> anyhow::Result<()> {
         _ = async { eth_sender_task_handle.await } => {
             panic!("Ethereum Sender actor is not supposed to finish its execution")
         },
+        _ = async { reorg_watcher_task_handle.await } => {
+            panic!("Reorg Watcher actor is not supposed to finish its execution")
+        },
+        _ = async { event_listener_task_handle.await } => {
+            panic!("Event Listener actor is not supposed to finish its execution")
+        },
+        res = async { reconciler_task_handle.await } => {
+            // the contract got ahead of us, keeping on would only submit reverting transactions
+            return res?;
+        },
         _ = async { stop_signal_receiver.next().await } => {
             log::warn!("Stop signal received, shutting down");
         }
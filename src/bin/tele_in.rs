@@ -0,0 +1,50 @@
+use fluidex_common::non_blocking_tracing;
+use futures::{channel::mpsc, executor::block_on, SinkExt, StreamExt};
+use regnbue_bridge::tele_in::{storage, DepositWatcher, Settings, UserSyncer};
+use std::cell::RefCell;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    dotenv::dotenv().ok();
+    let _guard = non_blocking_tracing::setup();
+    log::info!("regnbue-bridge tele_in started");
+
+    let mut conf = config_rs::Config::new();
+    let config_file = dotenv::var("TELE_IN_CONFIG").unwrap();
+    conf.merge(config_rs::File::with_name(&config_file)).unwrap();
+    let settings: Settings = conf.try_into().unwrap();
+    log::debug!("{:?}", settings);
+
+    // handle ctrl+c
+    let (stop_signal_sender, mut stop_signal_receiver) = mpsc::channel(256);
+    {
+        let stop_signal_sender = RefCell::new(stop_signal_sender.clone());
+        ctrlc::set_handler(move || {
+            let mut sender = stop_signal_sender.borrow_mut();
+            block_on(sender.send(true)).expect("crtlc signal send");
+        })
+        .expect("Error setting Ctrl-C handler");
+    }
+
+    let dbpool = storage::from_config(&settings).await?;
+
+    let user_syncer = UserSyncer::from_config_with_pool(&settings, dbpool.clone());
+    let user_syncer_task_handle = tokio::spawn(async move { user_syncer.run().await });
+
+    let deposit_watcher = DepositWatcher::from_config_with_pool(&settings, dbpool).await?;
+    let deposit_watcher_task_handle = tokio::spawn(async move { deposit_watcher.run().await });
+
+    tokio::select! {
+        _ = async { user_syncer_task_handle.await } => {
+            panic!("User Syncer actor is not supposed to finish its execution")
+        },
+        _ = async { deposit_watcher_task_handle.await } => {
+            panic!("Deposit Watcher actor is not supposed to finish its execution")
+        },
+        _ = async { stop_signal_receiver.next().await } => {
+            log::warn!("Stop signal received, shutting down");
+        }
+    };
+
+    Ok(())
+}
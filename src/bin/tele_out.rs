@@ -1,4 +1,50 @@
+use fluidex_common::non_blocking_tracing;
+use futures::{channel::mpsc, executor::block_on, SinkExt, StreamExt};
+use regnbue_bridge::tele_out::{storage, Settings, WithdrawProposer, WithdrawSender};
+use std::cell::RefCell;
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    unimplemented!()
+    dotenv::dotenv().ok();
+    let _guard = non_blocking_tracing::setup();
+    log::info!("regnbue-bridge tele_out started");
+
+    let mut conf = config_rs::Config::new();
+    let config_file = dotenv::var("TELE_OUT_CONFIG").unwrap();
+    conf.merge(config_rs::File::with_name(&config_file)).unwrap();
+    let settings: Settings = conf.try_into().unwrap();
+    log::debug!("{:?}", settings);
+
+    // handle ctrl+c
+    let (stop_signal_sender, mut stop_signal_receiver) = mpsc::channel(256);
+    {
+        let stop_signal_sender = RefCell::new(stop_signal_sender.clone());
+        ctrlc::set_handler(move || {
+            let mut sender = stop_signal_sender.borrow_mut();
+            block_on(sender.send(true)).expect("crtlc signal send");
+        })
+        .expect("Error setting Ctrl-C handler");
+    }
+
+    let dbpool = storage::from_config(&settings).await?;
+
+    let withdraw_proposer = WithdrawProposer::from_config_with_pool(&settings, dbpool.clone());
+    let withdraw_proposer_task_handle = tokio::spawn(async move { withdraw_proposer.run().await });
+
+    let withdraw_sender = WithdrawSender::from_config_with_pool(&settings, dbpool).await?;
+    let withdraw_sender_task_handle = tokio::spawn(async move { withdraw_sender.run().await });
+
+    tokio::select! {
+        _ = async { withdraw_proposer_task_handle.await } => {
+            panic!("Withdraw Proposer actor is not supposed to finish its execution")
+        },
+        _ = async { withdraw_sender_task_handle.await } => {
+            panic!("Withdraw Sender actor is not supposed to finish its execution")
+        },
+        _ = async { stop_signal_receiver.next().await } => {
+            log::warn!("Stop signal received, shutting down");
+        }
+    };
+
+    Ok(())
 }
@@ -1,4 +1,8 @@
+use crate::l1::{FeeSettings, RpcSettings, SignerSettings};
+use crate::storage::DecimalDbType;
 use serde::Deserialize;
+use std::num::NonZeroU64;
+use std::time::Duration;
 
 #[derive(Debug, Deserialize, Clone, PartialEq)]
 pub struct Settings {
@@ -7,7 +11,285 @@ pub struct Settings {
     pub contract_abi_file_path: String,
     pub confirmations: usize, // TODO: default
     pub web3_url: String,
-    pub keystore: String,
-    pub password: String,
+    #[serde(default)]
+    pub rpc: RpcSettings,
+    /// Plaintext keystore credentials of older configs, superseded by `signer`.
+    #[serde(default)]
+    pub keystore: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(default)]
+    pub signer: Option<SignerSettings>,
     pub chain_id: u64,
+    /// Runs fetching, checks, fee estimation and simulation only, recording the outcome in
+    /// `block_submission_shadow` instead of broadcasting or touching `l2_block`.
+    #[serde(default)]
+    pub shadow: bool,
+    #[serde(default)]
+    pub fee: FeeSettings,
+    #[serde(default)]
+    pub rebroadcast: RebroadcastSettings,
+    #[serde(default)]
+    pub batch: BatchSettings,
+    #[serde(default)]
+    pub reorg: ReorgSettings,
+    #[serde(default)]
+    pub reconcile: ReconcileSettings,
+    #[serde(default)]
+    pub event_listener: EventListenerSettings,
+    #[serde(default)]
+    pub guard: GuardSettings,
+    #[serde(default)]
+    pub proof: ProofSettings,
+    #[serde(default)]
+    pub consistency: ConsistencySettings,
+}
+
+/// Batching of consecutive proved blocks into one `submitBlocks` transaction.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct BatchSettings {
+    /// Maximum blocks per transaction, 1 disables batching.
+    pub size: usize,
+    /// Seconds to wait for a batch to fill up before sending a partial one.
+    pub max_wait: u64,
+    /// Batches fetched ahead of the one being sent, the fetcher waits once they are queued.
+    pub queue_size: usize,
+}
+
+impl Default for BatchSettings {
+    fn default() -> Self {
+        Self {
+            size: 1,
+            max_wait: 60,
+            queue_size: 1,
+        }
+    }
+}
+
+impl BatchSettings {
+    /// Converts `self.max_wait` into `Duration`.
+    pub fn max_wait(&self) -> Duration {
+        Duration::from_secs(self.max_wait)
+    }
+}
+
+/// When and how a stuck transaction gets replaced with a higher-priced one of the same nonce.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct RebroadcastSettings {
+    /// Seconds to wait for a transaction to be mined before replacing it.
+    pub timeout: u64,
+    /// Percentage added to the fees on each replacement, at least the 10 nodes require.
+    pub gas_bump_percent: u64,
+    /// Ceiling of `gasPrice` or `maxFeePerGas`, in gwei.
+    pub max_gas_price: u64,
+}
+
+impl Default for RebroadcastSettings {
+    fn default() -> Self {
+        Self {
+            timeout: 180,
+            gas_bump_percent: 12,
+            max_gas_price: 500,
+        }
+    }
+}
+
+impl RebroadcastSettings {
+    /// Converts `self.timeout` into `Duration`.
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout)
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct ReorgSettings {
+    /// L1 blocks after which a mined submission can no longer be reorged.
+    pub finality_depth: u64,
+    /// Seconds between two checks of the mined submissions.
+    pub check_interval: u64,
+}
+
+impl Default for ReorgSettings {
+    fn default() -> Self {
+        Self {
+            finality_depth: 12,
+            check_interval: 15,
+        }
+    }
+}
+
+impl ReorgSettings {
+    /// Converts `self.check_interval` into `Duration`.
+    pub fn check_interval(&self) -> Duration {
+        Duration::from_secs(self.check_interval)
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct ReconcileSettings {
+    /// Seconds between two reconciliations with the contract.
+    pub interval: u64,
+    /// `(uint256 blockId) view returns (uint256)` contract method giving a block's state root, not checked if unset.
+    pub state_root_method: Option<String>,
+}
+
+impl Default for ReconcileSettings {
+    fn default() -> Self {
+        Self {
+            interval: 300,
+            state_root_method: None,
+        }
+    }
+}
+
+impl ReconcileSettings {
+    /// Converts `self.interval` into `Duration`.
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval)
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum EventListenerMode {
+    /// `eth_getLogs` every `poll_interval`.
+    Polling,
+    /// `eth_getLogs` on every new head pushed by `ws_url`, a WebSocket URL or an IPC path.
+    Websocket,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct EventListenerSettings {
+    pub mode: EventListenerMode,
+    /// Defaults to `web3_url` when that one pushes subscriptions.
+    pub ws_url: Option<String>,
+    /// L1 block to start from when nothing has been synced yet, e.g. the contract deployment.
+    pub start_block: u64,
+    pub poll_interval: u64,
+    /// Most L1 blocks queried by a single `eth_getLogs`.
+    pub max_block_range: NonZeroU64,
+    pub event: String,
+    /// Name of the event parameter holding the block id.
+    pub block_id_param: String,
+}
+
+impl Default for EventListenerSettings {
+    fn default() -> Self {
+        Self {
+            mode: EventListenerMode::Polling,
+            ws_url: None,
+            start_block: 0,
+            poll_interval: 15,
+            max_block_range: NonZeroU64::new(1000).unwrap(),
+            event: "BlockSubmitted".to_string(),
+            block_id_param: "blockId".to_string(),
+        }
+    }
+}
+
+impl EventListenerSettings {
+    /// Converts `self.poll_interval` into `Duration`.
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_secs(self.poll_interval)
+    }
+}
+
+/// Spending limits of the operator account, in ETH, each one unlimited if unset.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct GuardSettings {
+    /// Highest fee a single transaction may pay, i.e. `gas * maxFeePerGas`.
+    pub max_fee_per_tx: Option<DecimalDbType>,
+    /// Highest fee all transactions of the last 24 hours may pay together.
+    pub daily_spend_cap: Option<DecimalDbType>,
+    /// Balance the account has to keep after paying a transaction's fee.
+    pub min_balance: Option<DecimalDbType>,
+    /// Seconds between two checks while submission is paused.
+    pub recheck_interval: u64,
+}
+
+impl Default for GuardSettings {
+    fn default() -> Self {
+        Self {
+            max_fee_per_tx: None,
+            daily_spend_cap: None,
+            min_balance: None,
+            recheck_interval: 60,
+        }
+    }
+}
+
+impl GuardSettings {
+    /// Converts `self.recheck_interval` into `Duration`.
+    pub fn recheck_interval(&self) -> Duration {
+        Duration::from_secs(self.recheck_interval)
+    }
+}
+
+/// Checks of a block's proof before it is submitted, all field elements are range checked regardless.
+#[derive(Debug, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct ProofSettings {
+    /// Expected number of public inputs, not checked if unset.
+    pub public_inputs_len: Option<usize>,
+    /// Expected number of elements of the serialized proof, not checked if unset.
+    pub proof_len: Option<usize>,
+    pub verifier: Option<VerifierSettings>,
+}
+
+/// External proof verifier, e.g. `snarkjs groth16 verify {vk} {public_inputs} {proof}`.
+/// `{vk}`, `{public_inputs}` and `{proof}` in `args` are replaced by paths to JSON files,
+/// the latter two holding arrays of decimal strings. A zero exit status accepts the proof.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct VerifierSettings {
+    pub command: String,
+    pub args: Vec<String>,
+    pub vk_path: String,
+    /// Seconds the verifier may run before it is killed and the proof held back.
+    #[serde(default = "default_verifier_timeout")]
+    pub timeout: u64,
+}
+
+fn default_verifier_timeout() -> u64 {
+    60
+}
+
+impl VerifierSettings {
+    /// Converts `self.timeout` into `Duration`.
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout)
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CommitmentHash {
+    Sha256,
+    Keccak256,
+}
+
+/// Cross-check of the first public input against `l2_block`, which has to equal
+/// `hash(old_root || new_root || hash(raw_public_data)) mod r` with 32-byte big-endian roots.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct ConsistencySettings {
+    pub enabled: bool,
+    pub hash: CommitmentHash,
+    /// State root before the first block, which has no previous `l2_block` row.
+    pub genesis_root: Option<String>,
+}
+
+impl Default for ConsistencySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            hash: CommitmentHash::Sha256,
+            genesis_root: None,
+        }
+    }
 }
@@ -0,0 +1,178 @@
+//! Cross-checks a proof's public input against the `l2_block` row it is meant to prove, so that
+//! a proof of some other state is never submitted for the block.
+
+use super::proof_checker::SCALAR_FIELD_MODULUS;
+use super::types::SubmitBlockArgs;
+use crate::block_submitter::config::{CommitmentHash, ConsistencySettings};
+use ethers::types::U256;
+use ethers::utils::keccak256;
+use sha2::{Digest, Sha256};
+
+/// Parses a field element stored as `0x`-prefixed hex or as decimal.
+pub fn parse_field_element(s: &str) -> Result<U256, anyhow::Error> {
+    Ok(match s.strip_prefix("0x") {
+        Some(hex) => U256::from_str_radix(hex, 16)?,
+        None => U256::from_dec_str(s)?,
+    })
+}
+
+/// What `l2_block` records about a block and about the one before it.
+#[derive(Debug)]
+pub struct BlockData<'a> {
+    pub old_root: Option<&'a str>,
+    pub new_root: &'a str,
+    pub raw_public_data: &'a [u8],
+}
+
+#[derive(Debug)]
+pub struct ConsistencyChecker {
+    hash: CommitmentHash,
+    genesis_root: Option<String>,
+    scalar_field_modulus: U256,
+}
+
+impl ConsistencyChecker {
+    pub fn from_config(config: &ConsistencySettings) -> Option<Self> {
+        if !config.enabled {
+            return None;
+        }
+        Some(Self {
+            hash: config.hash,
+            genesis_root: config.genesis_root.clone(),
+            scalar_field_modulus: U256::from_dec_str(SCALAR_FIELD_MODULUS).unwrap(),
+        })
+    }
+
+    fn digest(&self, data: &[u8]) -> [u8; 32] {
+        match self.hash {
+            CommitmentHash::Sha256 => {
+                let mut digest = [0u8; 32];
+                digest.copy_from_slice(&Sha256::digest(data));
+                digest
+            }
+            CommitmentHash::Keccak256 => keccak256(data),
+        }
+    }
+
+    /// The commitment `hash(old_root || new_root || hash(raw_public_data)) mod r` the first public input has to match.
+    pub fn commitment(&self, old_root: U256, new_root: U256, raw_public_data: &[u8]) -> U256 {
+        let mut preimage = [0u8; 96];
+        old_root.to_big_endian(&mut preimage[..32]);
+        new_root.to_big_endian(&mut preimage[32..64]);
+        preimage[64..].copy_from_slice(&self.digest(raw_public_data));
+        U256::from_big_endian(&self.digest(&preimage)) % self.scalar_field_modulus
+    }
+
+    /// Returns how `args` disagrees with `block`, if it does.
+    pub fn check(&self, args: &SubmitBlockArgs, block: &BlockData) -> Result<Option<String>, anyhow::Error> {
+        let old_root = match block.old_root.or_else(|| self.genesis_root.as_deref()) {
+            Some(root) => parse_field_element(root)?,
+            None => {
+                log::warn!("no previous root for block {}, public inputs not cross-checked", args.block_id);
+                return Ok(None);
+            }
+        };
+        let new_root = parse_field_element(block.new_root)?;
+        let expected = self.commitment(old_root, new_root, block.raw_public_data);
+
+        Ok(match args.public_inputs.first() {
+            Some(actual) if *actual == expected => None,
+            Some(actual) => Some(format!(
+                "public input {:#x} does not commit to l2_block, expected {:#x}",
+                actual, expected
+            )),
+            None => Some("no public input to cross-check against l2_block".to_string()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checker(hash: CommitmentHash, genesis_root: Option<&str>) -> ConsistencyChecker {
+        ConsistencyChecker::from_config(&ConsistencySettings {
+            enabled: true,
+            hash,
+            genesis_root: genesis_root.map(str::to_string),
+        })
+        .unwrap()
+    }
+
+    fn args(public_inputs: Vec<U256>) -> SubmitBlockArgs {
+        SubmitBlockArgs {
+            block_id: U256::from(1),
+            public_inputs,
+            serialized_proof: vec![],
+        }
+    }
+
+    fn preimage(old_root: u64, new_root: u64, data_digest: &[u8]) -> Vec<u8> {
+        let mut preimage = vec![0u8; 64];
+        preimage[24..32].copy_from_slice(&old_root.to_be_bytes());
+        preimage[56..64].copy_from_slice(&new_root.to_be_bytes());
+        preimage.extend_from_slice(data_digest);
+        preimage
+    }
+
+    #[test]
+    fn commitment_hashes_big_endian_roots_and_the_data_digest() {
+        let modulus = U256::from_dec_str(SCALAR_FIELD_MODULUS).unwrap();
+        let data = b"public data";
+
+        let expected = U256::from_big_endian(&Sha256::digest(&preimage(1, 2, &Sha256::digest(data)))) % modulus;
+        let actual = checker(CommitmentHash::Sha256, None).commitment(U256::from(1), U256::from(2), data);
+        assert_eq!(actual, expected);
+
+        let expected = U256::from_big_endian(&keccak256(preimage(1, 2, &keccak256(data)))) % modulus;
+        let actual = checker(CommitmentHash::Keccak256, None).commitment(U256::from(1), U256::from(2), data);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn commitment_is_reduced_into_the_scalar_field() {
+        let checker = checker(CommitmentHash::Sha256, None);
+        for data in [&b""[..], b"a", b"b", b"c", b"d"] {
+            assert!(checker.commitment(U256::zero(), U256::one(), data) < checker.scalar_field_modulus);
+        }
+    }
+
+    #[test]
+    fn commitment_depends_on_root_order() {
+        let checker = checker(CommitmentHash::Sha256, None);
+        assert_ne!(
+            checker.commitment(U256::from(1), U256::from(2), b""),
+            checker.commitment(U256::from(2), U256::from(1), b"")
+        );
+    }
+
+    #[test]
+    fn check_compares_the_first_public_input() {
+        let checker = checker(CommitmentHash::Sha256, None);
+        let block = BlockData {
+            old_root: Some("0x1"),
+            new_root: "2",
+            raw_public_data: b"data",
+        };
+        let expected = checker.commitment(U256::from(1), U256::from(2), b"data");
+
+        assert_eq!(checker.check(&args(vec![expected, U256::zero()]), &block).unwrap(), None);
+        assert!(checker.check(&args(vec![expected + 1]), &block).unwrap().is_some());
+        assert!(checker.check(&args(vec![]), &block).unwrap().is_some());
+    }
+
+    #[test]
+    fn check_falls_back_to_the_genesis_root() {
+        let block = BlockData {
+            old_root: None,
+            new_root: "2",
+            raw_public_data: b"",
+        };
+        let unchecked = checker(CommitmentHash::Sha256, None);
+        assert_eq!(unchecked.check(&args(vec![]), &block).unwrap(), None);
+
+        let checker = checker(CommitmentHash::Sha256, Some("7"));
+        let expected = checker.commitment(U256::from(7), U256::from(2), b"");
+        assert_eq!(checker.check(&args(vec![expected]), &block).unwrap(), None);
+    }
+}
@@ -0,0 +1,160 @@
+//! Spending limits of the operator account. A transaction breaching one is not sent, submission
+//! pauses instead and an alert stays open in `operator_alert` until the limits are met again.
+
+use crate::block_submitter::config::GuardSettings;
+use crate::block_submitter::storage::models;
+use crate::l1::{units, L1Provider};
+use crate::storage::{DecimalDbType, PoolType};
+use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use std::fmt;
+use std::time::Duration;
+
+const ETH_DECIMALS: u32 = 18;
+
+#[derive(Debug)]
+pub enum Breach {
+    MaxFee { fee: U256, max: U256 },
+    DailyCap { spent: U256, fee: U256, cap: U256 },
+    MinBalance { balance: U256, fee: U256, min: U256 },
+}
+
+impl Breach {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Breach::MaxFee { .. } => "max_fee_per_tx",
+            Breach::DailyCap { .. } => "daily_spend_cap",
+            Breach::MinBalance { .. } => "min_balance",
+        }
+    }
+}
+
+impl fmt::Display for Breach {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let eth = |wei: &U256| units::from_base_units(*wei, ETH_DECIMALS).map_or_else(|_| format!("{} wei", wei), |d| format!("{} ETH", d));
+        match self {
+            Breach::MaxFee { fee, max } => write!(f, "fee {} exceeds the per-tx max of {}", eth(fee), eth(max)),
+            Breach::DailyCap { spent, fee, cap } => write!(
+                f,
+                "fee {} on top of {} spent in the last 24h exceeds the daily cap of {}",
+                eth(fee),
+                eth(spent),
+                eth(cap)
+            ),
+            Breach::MinBalance { balance, fee, min } => write!(
+                f,
+                "fee {} would take the balance of {} below the minimum of {}",
+                eth(fee),
+                eth(balance),
+                eth(min)
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Breach {}
+
+#[derive(Debug, Clone)]
+pub struct SpendGuard {
+    max_fee_per_tx: Option<U256>,
+    daily_spend_cap: Option<U256>,
+    min_balance: Option<U256>,
+    pub recheck_interval: Duration,
+}
+
+fn to_wei(eth: &Option<DecimalDbType>) -> Result<Option<U256>, anyhow::Error> {
+    eth.map(|eth| units::to_base_units(eth, ETH_DECIMALS)).transpose()
+}
+
+/// Most `tx` can pay, `gas * maxFeePerGas` or `gas * gasPrice`.
+fn max_fee(tx: &TypedTransaction) -> U256 {
+    let price = match tx {
+        TypedTransaction::Legacy(inner) => inner.gas_price,
+        TypedTransaction::Eip2930(inner) => inner.tx.gas_price,
+        TypedTransaction::Eip1559(inner) => inner.max_fee_per_gas,
+    };
+    tx.gas().copied().unwrap_or_default() * price.unwrap_or_default()
+}
+
+impl SpendGuard {
+    pub fn from_config(config: &GuardSettings) -> Result<Self, anyhow::Error> {
+        Ok(Self {
+            max_fee_per_tx: to_wei(&config.max_fee_per_tx)?,
+            daily_spend_cap: to_wei(&config.daily_spend_cap)?,
+            min_balance: to_wei(&config.min_balance)?,
+            recheck_interval: config.recheck_interval(),
+        })
+    }
+
+    /// Checks a filled `tx` against the limits before `account` sends it.
+    pub async fn check(
+        &self,
+        connpool: &PoolType,
+        client: &L1Provider,
+        account: Address,
+        tx: &TypedTransaction,
+    ) -> Result<Option<Breach>, anyhow::Error> {
+        let fee = max_fee(tx);
+
+        if let Some(max) = self.max_fee_per_tx {
+            if fee > max {
+                return Ok(Some(Breach::MaxFee { fee, max }));
+            }
+        }
+
+        if let Some(cap) = self.daily_spend_cap {
+            let spent = spent_last_day(connpool).await?;
+            if spent + fee > cap {
+                return Ok(Some(Breach::DailyCap { spent, fee, cap }));
+            }
+        }
+
+        if let Some(min) = self.min_balance {
+            let balance = client.get_balance(account, None).await?;
+            if balance < fee + min {
+                return Ok(Some(Breach::MinBalance { balance, fee, min }));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Upper bound of the fees paid in the last 24 hours: the most expensive attempt of each nonce,
+/// as replacements of a transaction share its nonce and only one of them is ever mined.
+async fn spent_last_day(connpool: &PoolType) -> Result<U256, anyhow::Error> {
+    let query = format!(
+        "select coalesce(sum(fee), 0) from (
+            select max(gas_limit::numeric * coalesce(max_fee_per_gas, gas_price, 0)) as fee from {}
+            where created_time > CURRENT_TIMESTAMP - interval '1 day' and status <> 'dropped'
+            group by nonce
+        ) as per_nonce",
+        models::tablenames::L1_PENDING_TX
+    );
+    let spent: DecimalDbType = sqlx::query_scalar(&query).fetch_one(connpool).await?;
+    units::to_base_units(spent, 0)
+}
+
+/// Opens an alert for `breach`, or refreshes the open one of its kind.
+pub async fn raise(connpool: &PoolType, breach: &Breach) -> Result<(), anyhow::Error> {
+    let stmt = format!(
+        "insert into {} (kind, detail) values ($1, $2)
+        on conflict (kind) where cleared_time is null do update set detail = excluded.detail, updated_time = CURRENT_TIMESTAMP",
+        models::tablenames::OPERATOR_ALERT
+    );
+    sqlx::query(&stmt)
+        .bind(breach.kind())
+        .bind(breach.to_string())
+        .execute(connpool)
+        .await?;
+    Ok(())
+}
+
+/// Clears the open alerts, returns how many there were.
+pub async fn clear(connpool: &PoolType) -> Result<u64, anyhow::Error> {
+    let stmt = format!(
+        "update {} set cleared_time = CURRENT_TIMESTAMP, updated_time = CURRENT_TIMESTAMP where cleared_time is null",
+        models::tablenames::OPERATOR_ALERT
+    );
+    Ok(sqlx::query(&stmt).execute(connpool).await?.rows_affected())
+}
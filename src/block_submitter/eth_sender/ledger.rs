@@ -0,0 +1,133 @@
+//! Ledger of L1 transactions that have been signed but not yet confirmed,
+//! so that a restart resumes them instead of submitting the block again.
+
+use crate::block_submitter::storage::models::{self, L1PendingTx, L1TxStatus};
+use crate::storage::PoolType;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Bytes, TransactionReceipt, H256, U256};
+
+fn to_i64(v: Option<&U256>) -> Option<i64> {
+    v.map(|v| v.as_u64() as i64)
+}
+
+pub async fn record(
+    connpool: &PoolType,
+    (block_id, block_count): (i64, usize),
+    tx: &TypedTransaction,
+    tx_hash: H256,
+    raw_tx: &Bytes,
+) -> Result<L1PendingTx, anyhow::Error> {
+    let (gas_price, max_fee_per_gas, max_priority_fee_per_gas) = match tx {
+        TypedTransaction::Legacy(inner) => (inner.gas_price, None, None),
+        TypedTransaction::Eip2930(inner) => (inner.tx.gas_price, None, None),
+        TypedTransaction::Eip1559(inner) => (None, inner.max_fee_per_gas, inner.max_priority_fee_per_gas),
+    };
+
+    let stmt = format!(
+        "insert into {} (block_id, nonce, tx_hash, gas_limit, gas_price, max_fee_per_gas, max_priority_fee_per_gas, raw_tx, tx_request, block_count)
+        values ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        returning *",
+        models::tablenames::L1_PENDING_TX
+    );
+    let row = sqlx::query_as(&stmt)
+        .bind(block_id)
+        .bind(to_i64(tx.nonce()))
+        .bind(format!("{:#x}", tx_hash))
+        .bind(to_i64(tx.gas()))
+        .bind(to_i64(gas_price.as_ref()))
+        .bind(to_i64(max_fee_per_gas.as_ref()))
+        .bind(to_i64(max_priority_fee_per_gas.as_ref()))
+        .bind(raw_tx.as_ref())
+        .bind(serde_json::to_value(tx)?)
+        .bind(block_count as i32)
+        .fetch_one(connpool)
+        .await?;
+    Ok(row)
+}
+
+pub async fn load_pending(connpool: &PoolType) -> Result<Vec<L1PendingTx>, anyhow::Error> {
+    let query = format!(
+        "select * from {} where status = $1 order by nonce, id",
+        models::tablenames::L1_PENDING_TX
+    );
+    Ok(sqlx::query_as(&query).bind(L1TxStatus::Pending).fetch_all(connpool).await?)
+}
+
+/// Pending transactions grouped by nonce, replacements of one transaction share its nonce.
+pub async fn load_pending_by_nonce(connpool: &PoolType) -> Result<Vec<Vec<L1PendingTx>>, anyhow::Error> {
+    let mut groups: Vec<Vec<L1PendingTx>> = Vec::new();
+    for pending in load_pending(connpool).await? {
+        match groups.last_mut() {
+            Some(group) if group[0].nonce == pending.nonce => group.push(pending),
+            _ => groups.push(vec![pending]),
+        }
+    }
+    Ok(groups)
+}
+
+pub async fn max_pending_block_id(connpool: &PoolType) -> Result<Option<i64>, anyhow::Error> {
+    let query = format!(
+        "select max(block_id + block_count - 1) from {} where status = $1",
+        models::tablenames::L1_PENDING_TX
+    );
+    Ok(sqlx::query_scalar(&query).bind(L1TxStatus::Pending).fetch_one(connpool).await?)
+}
+
+/// Settles a nonce: `mined` is the transaction that got mined with `status`, `Mined` or `Failed`,
+/// the others sharing its nonce were replaced.
+pub async fn settle(
+    connpool: &PoolType,
+    mined: &L1PendingTx,
+    receipt: &TransactionReceipt,
+    status: L1TxStatus,
+) -> Result<(), anyhow::Error> {
+    let stmt = format!(
+        "update {} set status = case when id = $1 then $2 else $3 end,
+            l1_block_number = case when id = $1 then $6 end,
+            l1_block_hash = case when id = $1 then $7 end,
+            updated_time = CURRENT_TIMESTAMP
+        where nonce = $4 and status = $5",
+        models::tablenames::L1_PENDING_TX
+    );
+    sqlx::query(&stmt)
+        .bind(mined.id)
+        .bind(status)
+        .bind(L1TxStatus::Replaced)
+        .bind(mined.nonce)
+        .bind(L1TxStatus::Pending)
+        .bind(receipt.block_number.map(|n| n.as_u64() as i64))
+        .bind(receipt.block_hash.map(|h| format!("{:#x}", h)))
+        .execute(connpool)
+        .await?;
+    Ok(())
+}
+
+pub async fn load_mined(connpool: &PoolType) -> Result<Vec<L1PendingTx>, anyhow::Error> {
+    let query = format!(
+        "select * from {} where status = $1 order by nonce",
+        models::tablenames::L1_PENDING_TX
+    );
+    Ok(sqlx::query_as(&query).bind(L1TxStatus::Mined).fetch_all(connpool).await?)
+}
+
+/// First block of the transactions mined after L1 block `l1_block`, which a read at `l1_block` does not see yet.
+pub async fn first_block_mined_after(connpool: &PoolType, l1_block: i64) -> Result<Option<i64>, anyhow::Error> {
+    let query = format!(
+        "select min(block_id) from {} where status = $1 and l1_block_number > $2",
+        models::tablenames::L1_PENDING_TX
+    );
+    Ok(sqlx::query_scalar(&query)
+        .bind(L1TxStatus::Mined)
+        .bind(l1_block)
+        .fetch_one(connpool)
+        .await?)
+}
+
+pub async fn set_status(connpool: &PoolType, id: i32, status: L1TxStatus) -> Result<(), anyhow::Error> {
+    let stmt = format!(
+        "update {} set status = $1, updated_time = CURRENT_TIMESTAMP where id = $2",
+        models::tablenames::L1_PENDING_TX
+    );
+    sqlx::query(&stmt).bind(status).bind(id).execute(connpool).await?;
+    Ok(())
+}
@@ -1,23 +1,69 @@
 use super::types::{ContractCall, SubmitBlockArgs};
+use crate::block_submitter::storage::{
+    self,
+    models::{Cursor, L1PendingTx, L1TxStatus, ShadowOutcome, SubmissionStatus},
+};
 use crate::block_submitter::Settings;
-use crate::contracts;
+use crate::contracts::{self, RevertDecoder};
+use crate::l1::{heads, transport, FeeEstimator, L1Provider, L1Signer, SignerSettings};
 use crate::storage::PoolType;
-use crossbeam_channel::Receiver;
+use anyhow::anyhow;
 use ethers::abi::Abi;
 use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
 use ethers::types::H256;
 use fluidex_common::db::models;
-use std::convert::TryFrom;
+use guard::{Breach, SpendGuard};
+use rebroadcast::RebroadcastPolicy;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc::Receiver, watch};
 
-type SignedClient = SignerMiddleware<Provider<Http>, LocalWallet>;
+mod guard;
+pub(crate) mod ledger;
+mod rebroadcast;
+mod simulation;
+
+const RECEIPT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Wait before simulating a reverted block again, doubled on each further revert up to the max.
+const REVERT_BACKOFF_MIN: Duration = Duration::from_secs(5);
+const REVERT_BACKOFF_MAX: Duration = Duration::from_secs(300);
+
+/// The first block of the last call that reverted in simulation. It is simulated again only
+/// after `delay`, unless it has been re-proved meanwhile.
+#[derive(Debug)]
+struct RevertedBlock {
+    args: SubmitBlockArgs,
+    reason: String,
+    delay: Duration,
+    retry_at: Instant,
+}
+
+/// Where the attempts at a nonce stand after a look at the chain.
+#[derive(Debug)]
+enum Progress {
+    Pending,
+    Confirmed,
+    /// Given up on, the blocks have been put back up for submission.
+    Abandoned(anyhow::Error),
+}
 
 #[derive(Debug)]
 pub struct EthSender {
     connpool: PoolType,
-    client: SignedClient,
+    client: L1Provider,
+    signer: L1Signer,
     account: Address,
-    contract: Contract<SignedClient>,
+    contract: Contract<L1Provider>,
+    revert_decoder: RevertDecoder,
     confirmations: usize,
+    fee_estimator: FeeEstimator,
+    rebroadcast: RebroadcastPolicy,
+    guard: SpendGuard,
+    /// Latest L1 block number, pushed when `web3_url` is a WebSocket or IPC endpoint.
+    heads: watch::Receiver<u64>,
+    shadow: bool,
+    reverted: Mutex<Option<RevertedBlock>>,
 }
 
 impl EthSender {
@@ -25,24 +71,39 @@ impl EthSender {
         let address = config.contract_address.parse::<Address>()?;
         let abi: Abi = contracts::get_abi(&config.contract_abi_file_path)?;
 
-        let client = Provider::<Http>::try_from(config.web3_url.as_str())?;
-        let wallet = LocalWallet::decrypt_keystore(config.keystore.as_str(), config.password.as_str())?.with_chain_id(config.chain_id);
-        let account = wallet.address();
-        let client = SignerMiddleware::new(client, wallet);
+        let client = transport::provider(&config.web3_url, &config.rpc).await?;
+        let signer = SignerSettings::or_keystore(&config.signer, &config.keystore, &config.password)?;
+        let signer = L1Signer::from_config(&signer, config.chain_id).await?;
+        let account = signer.address();
 
         let contract = Contract::new(address, abi, client.clone());
+        let heads = heads::watch(config.web3_url.clone(), client.clone(), RECEIPT_POLL_INTERVAL);
 
         Ok(Self {
             connpool,
             client,
+            signer,
             account,
             contract,
+            revert_decoder: contracts::get_revert_decoder(&config.contract_abi_file_path)?,
             confirmations: config.confirmations,
+            fee_estimator: FeeEstimator::from_config(&config.fee),
+            rebroadcast: RebroadcastPolicy::from_config(&config.rebroadcast),
+            guard: SpendGuard::from_config(&config.guard)?,
+            heads,
+            shadow: config.shadow,
+            reverted: Mutex::new(None),
         })
     }
 
-    pub async fn run(&self, rx: Receiver<ContractCall>) {
-        for call in rx.iter() {
+    pub async fn run(&self, mut rx: Receiver<ContractCall>) {
+        if self.shadow {
+            log::warn!("shadow mode, nothing will be broadcast");
+        } else if let Err(e) = self.resume_pending().await {
+            log::error!("resume pending transactions: {:?}", e);
+        }
+
+        while let Some(call) = rx.recv().await {
             log::debug!("{:?}", call);
             if let Err(e) = self.run_inner(call).await {
                 log::error!("{:?}", e);
@@ -50,43 +111,442 @@ impl EthSender {
         }
     }
 
+    /// Resumes the transactions left in the ledger by a previous run: mined ones are confirmed,
+    /// in-flight ones are rebroadcast and ones whose nonce got used otherwise are re-queued.
+    async fn resume_pending(&self) -> Result<(), anyhow::Error> {
+        for attempts in ledger::load_pending_by_nonce(&self.connpool).await? {
+            for pending in &attempts {
+                log::info!("rebroadcasting tx {} of block {}", pending.tx_hash, pending.block_id);
+                if let Err(e) = self.client.send_raw_transaction(pending.raw_tx.clone().into()).await {
+                    // most likely it is mined already or the node still has it in its mempool
+                    log::warn!("rebroadcast tx {}: {:?}", pending.tx_hash, e);
+                }
+            }
+
+            if let Err(e) = self.confirm(attempts).await {
+                log::error!("{:?}", e);
+            }
+        }
+
+        Ok(())
+    }
+
     async fn run_inner(&self, call: ContractCall) -> Result<(), anyhow::Error> {
-        match call {
-            ContractCall::SubmitBlock(args) => {
-                let tx_hash = match self.submit_block(args.clone()).await? {
-                    // https://stackoverflow.com/questions/57350082/to-convert-a-ethereum-typesh256-to-string-in-rust
-                    Some(h) => format!("{:#x}", h),
-                    None => "".to_string(),
-                };
-
-                let stmt = format!(
-                    "update {} set status = $1, l1_tx_hash = $2 where block_id = $3",
-                    models::tablenames::L2_BLOCK
-                );
-                sqlx::query(&stmt)
-                    .bind(models::l2_block::BlockStatus::Verified)
-                    .bind(tx_hash)
-                    .bind(args.block_id.as_u64() as i64)
-                    .execute(&self.connpool)
-                    .await?;
+        if self.shadow {
+            return self.shadow_submit(call).await;
+        }
+
+        let pending = loop {
+            match self.submit(call.clone()).await {
+                Ok(pending) => break pending,
+                Err(e) => match e.downcast_ref::<Breach>() {
+                    Some(breach) => self.pause(call.first_block_id(), breach).await?,
+                    None => {
+                        // the task fetcher moved on already, have it fetch the blocks again
+                        storage::rewind_progress(&self.connpool, call.first_block_id()).await?;
+                        return Err(e);
+                    }
+                },
             }
         };
+        if guard::clear(&self.connpool).await? > 0 {
+            log::info!("spend guard cleared, submission resumed at block {}", pending.block_id);
+        }
+        self.confirm(vec![pending]).await
+    }
 
+    /// Holds submission back while a spending limit is breached, the blocks are tried again afterwards.
+    async fn pause(&self, block_id: i64, breach: &Breach) -> Result<(), anyhow::Error> {
+        log::error!(
+            "spend guard tripped, submission paused. kind={} block_id={} account={:#x} detail=\"{}\"",
+            breach.kind(),
+            block_id,
+            self.account,
+            breach
+        );
+        guard::raise(&self.connpool, breach).await?;
+        tokio::time::sleep(self.guard.recheck_interval).await;
         Ok(())
     }
 
-    pub async fn submit_block(&self, args: SubmitBlockArgs) -> Result<Option<H256>, anyhow::Error> {
-        let call = self
-            .contract
-            .method::<_, H256>("submitBlock", (args.block_id, args.public_inputs, args.serialized_proof))
-            .unwrap()
-            .from(self.account);
-        // ganache does not support EIP-1559
-        #[cfg(feature = "ganache")]
-        let call = call.legacy();
-        let pending_tx = call.send().await?;
-        let receipt = pending_tx.confirmations(self.confirmations).await?;
-        log::info!("block {:?} confirmed. receipt: {:?}.", args.block_id, receipt);
-        Ok(receipt.map(|r| r.transaction_hash))
+    /// Builds the transaction submitting `call`, with fees and nonce but not yet simulated nor filled.
+    async fn build_tx(&self, call: ContractCall) -> Result<TypedTransaction, anyhow::Error> {
+        let call = match call {
+            ContractCall::SubmitBlock(args) => self
+                .contract
+                .method::<_, H256>("submitBlock", (args.block_id, args.public_inputs, args.serialized_proof))?,
+            ContractCall::SubmitBlocks(batch) => {
+                let mut block_ids = Vec::with_capacity(batch.len());
+                let mut public_inputs = Vec::with_capacity(batch.len());
+                let mut serialized_proofs = Vec::with_capacity(batch.len());
+                for args in batch {
+                    block_ids.push(args.block_id);
+                    public_inputs.push(args.public_inputs);
+                    serialized_proofs.push(args.serialized_proof);
+                }
+                self.contract
+                    .method::<_, H256>("submitBlocks", (block_ids, public_inputs, serialized_proofs))?
+            }
+        }
+        .from(self.account);
+
+        let mut tx = self.fee_estimator.apply(&self.client, call.tx).await?;
+        let nonce = self
+            .client
+            .get_transaction_count(self.account, Some(BlockNumber::Pending.into()))
+            .await?;
+        tx.set_nonce(nonce);
+        Ok(tx)
+    }
+
+    pub async fn submit(&self, call: ContractCall) -> Result<L1PendingTx, anyhow::Error> {
+        let blocks = (call.first_block_id(), call.block_count());
+        if let Some(wait) = self.revert_backoff(call.first()) {
+            tokio::time::sleep(wait).await;
+        }
+        let first = call.first().clone();
+        let mut tx = self.build_tx(call).await?;
+
+        // the revert may be temporary, `run_inner` has the blocks fetched again
+        if let Some(reason) = simulation::simulate(&self.client, &self.revert_decoder, &tx).await? {
+            if !self.note_revert(first, &reason) {
+                simulation::record_revert(&self.connpool, blocks, &reason).await?;
+            }
+            return Err(anyhow!("blocks {}..+{} would revert: {}", blocks.0, blocks.1, reason));
+        }
+        *self.reverted.lock().unwrap() = None;
+        self.client.fill_transaction(&mut tx, None).await?;
+        if let Some(breach) = self.guard.check(&self.connpool, &self.client, self.account, &tx).await? {
+            return Err(breach.into());
+        }
+
+        let pending = self.sign_and_send(blocks, &tx).await?;
+        storage::set_progress(&self.connpool, Cursor::Sent, pending.last_block_id()).await?;
+        storage::transition(&self.connpool, pending.block_range(), SubmissionStatus::Submitting).await?;
+        Ok(pending)
+    }
+
+    /// How long to hold `first` back after it reverted in simulation.
+    fn revert_backoff(&self, first: &SubmitBlockArgs) -> Option<Duration> {
+        let reverted = self.reverted.lock().unwrap();
+        reverted
+            .as_ref()
+            .filter(|reverted| reverted.args == *first)
+            .map(|reverted| reverted.retry_at.saturating_duration_since(Instant::now()))
+    }
+
+    /// Backs off from `first` after a revert, returns whether it reverted the same way last time.
+    fn note_revert(&self, first: SubmitBlockArgs, reason: &str) -> bool {
+        let mut reverted = self.reverted.lock().unwrap();
+        let (delay, repeated) = match reverted.as_ref() {
+            Some(last) if last.args == first && last.reason == reason => ((last.delay * 2).min(REVERT_BACKOFF_MAX), true),
+            _ => (REVERT_BACKOFF_MIN, false),
+        };
+        *reverted = Some(RevertedBlock {
+            args: first,
+            reason: reason.to_string(),
+            delay,
+            retry_at: Instant::now() + delay,
+        });
+        repeated
+    }
+
+    /// Goes through `submit` up to signing, then records what would have been sent.
+    async fn shadow_submit(&self, call: ContractCall) -> Result<(), anyhow::Error> {
+        let blocks = (call.first_block_id(), call.block_count());
+        let mut tx = self.build_tx(call).await?;
+
+        let revert_reason = simulation::simulate(&self.client, &self.revert_decoder, &tx).await?;
+        let mut guard_breach = None;
+        if revert_reason.is_none() {
+            self.client.fill_transaction(&mut tx, None).await?;
+            guard_breach = self
+                .guard
+                .check(&self.connpool, &self.client, self.account, &tx)
+                .await?
+                .map(|breach| breach.to_string());
+        }
+        let outcome = ShadowOutcome {
+            tx_request: Some(serde_json::to_value(&tx)?),
+            gas_limit: tx.gas().map(|gas| gas.as_u64() as i64),
+            revert_reason,
+            guard_breach,
+            rejected_reason: None,
+        };
+
+        log::info!(
+            "shadow: blocks {}..+{} would be submitted with gas {:?}, revert: {:?}, guard: {:?}",
+            blocks.0,
+            blocks.1,
+            outcome.gas_limit,
+            outcome.revert_reason,
+            outcome.guard_breach
+        );
+        storage::record_shadow(&self.connpool, blocks, &outcome).await
+    }
+
+    /// Signs `tx`, records it in the ledger and only then broadcasts it. A failed broadcast is
+    /// still returned as pending, the tx may have reached a node before the error.
+    async fn sign_and_send(&self, blocks: (i64, usize), tx: &TypedTransaction) -> Result<L1PendingTx, anyhow::Error> {
+        let raw_tx = self.signer.sign(tx).await?;
+        let tx_hash = H256::from(ethers::utils::keccak256(&raw_tx));
+
+        let pending = ledger::record(&self.connpool, blocks, tx, tx_hash, &raw_tx).await?;
+        if let Err(e) = self.client.send_raw_transaction(raw_tx).await {
+            // `confirm` finds it mined, broadcasts it again or sees its nonce used otherwise
+            log::warn!("broadcast tx {:#x} of block {}: {:?}", tx_hash, pending.block_id, e);
+            return Ok(pending);
+        }
+        log::info!(
+            "blocks {}..={} sent in tx {:#x} with nonce {}",
+            pending.block_id,
+            pending.last_block_id(),
+            tx_hash,
+            pending.nonce
+        );
+        Ok(pending)
+    }
+
+    /// Waits until one of `attempts`, which all share a nonce, is mined and confirmed, then records
+    /// it on its block. Replaces the latest attempt with a higher-priced one whenever it gets stuck.
+    async fn confirm(&self, mut attempts: Vec<L1PendingTx>) -> Result<(), anyhow::Error> {
+        let mut last_broadcast = Instant::now();
+        let mut heads = self.heads.clone();
+        loop {
+            next_head(&mut heads).await;
+
+            match self.check_attempts(&mut attempts, &mut last_broadcast).await {
+                Ok(Progress::Pending) => {}
+                Ok(Progress::Confirmed) => return Ok(()),
+                Ok(Progress::Abandoned(e)) => return Err(e),
+                // most likely a node or database hiccup, tried again on the next head
+                Err(e) => log::error!("confirm nonce {} of block {}: {:?}", attempts[0].nonce, attempts[0].block_id, e),
+            }
+        }
+    }
+
+    /// Looks at `attempts` once: whether one is mined, whether their nonce got used by another
+    /// transaction, and whether the latest one is due for a replacement.
+    async fn check_attempts(&self, attempts: &mut Vec<L1PendingTx>, last_broadcast: &mut Instant) -> Result<Progress, anyhow::Error> {
+        let nonce = U256::from(attempts[0].nonce);
+        let block_id = attempts[0].block_id;
+
+        if let Some(mined) = self.find_mined(attempts).await? {
+            return self.finalize(mined).await;
+        }
+
+        if self.client.get_transaction_count(self.account, None).await? > nonce {
+            // the nonce may have been used between both checks by one of ours
+            if self.find_mined(attempts).await?.is_some() {
+                return Ok(Progress::Pending);
+            }
+            for pending in attempts.iter() {
+                ledger::set_status(&self.connpool, pending.id, L1TxStatus::Dropped).await?;
+            }
+            storage::rewind_progress(&self.connpool, block_id).await?;
+            storage::transition(&self.connpool, attempts[0].block_range(), SubmissionStatus::Uncommitted).await?;
+            return Ok(Progress::Abandoned(anyhow!(
+                "nonce {} of block {} was used by another transaction",
+                nonce,
+                block_id
+            )));
+        }
+
+        if last_broadcast.elapsed() < self.rebroadcast.timeout {
+            return Ok(Progress::Pending);
+        }
+        *last_broadcast = Instant::now();
+
+        let latest = attempts.last().unwrap();
+        let latest_hash = latest.tx_hash.parse::<H256>()?;
+        if self.client.get_transaction(latest_hash).await?.is_none() {
+            // no node has it, its broadcast failed or it fell out of the mempool
+            log::warn!("tx {} of block {} is unknown, broadcasting it again", latest.tx_hash, block_id);
+            if let Err(e) = self.client.send_raw_transaction(latest.raw_tx.clone().into()).await {
+                log::warn!("rebroadcast tx {}: {:?}", latest.tx_hash, e);
+            }
+            return Ok(Progress::Pending);
+        }
+        let tx: TypedTransaction = match &latest.tx_request {
+            Some(request) => serde_json::from_value(request.clone())?,
+            None => return Ok(Progress::Pending),
+        };
+        let replacement = match self.rebroadcast.bump(&tx) {
+            Some(replacement) => replacement,
+            None => {
+                log::warn!("tx {} of block {} is stuck at the gas price ceiling", latest.tx_hash, block_id);
+                return Ok(Progress::Pending);
+            }
+        };
+        if let Some(breach) = self.guard.check(&self.connpool, &self.client, self.account, &replacement).await? {
+            log::error!(
+                "spend guard tripped, tx not replaced. kind={} block_id={} tx={} detail=\"{}\"",
+                breach.kind(),
+                block_id,
+                latest.tx_hash,
+                breach
+            );
+            guard::raise(&self.connpool, &breach).await?;
+            return Ok(Progress::Pending);
+        }
+        log::warn!("tx {} of block {} is stuck, replacing it", latest.tx_hash, block_id);
+        match self
+            .sign_and_send((latest.block_id, latest.block_count as usize), &replacement)
+            .await
+        {
+            Ok(pending) => attempts.push(pending),
+            Err(e) => log::error!("replace tx {}: {:?}", latest.tx_hash, e),
+        }
+        Ok(Progress::Pending)
+    }
+
+    /// Returns the attempt that got mined, whether it succeeded or reverted.
+    async fn find_mined(&self, attempts: &[L1PendingTx]) -> Result<Option<L1PendingTx>, anyhow::Error> {
+        for pending in attempts {
+            let tx_hash = pending.tx_hash.parse::<H256>()?;
+            if self.client.get_transaction_receipt(tx_hash).await?.is_some() {
+                return Ok(Some(pending.clone()));
+            }
+        }
+        Ok(None)
     }
+
+    /// Records a mined transaction once it has enough confirmations. It is still pending if it left the chain meanwhile.
+    async fn finalize(&self, mined: L1PendingTx) -> Result<Progress, anyhow::Error> {
+        let tx_hash = mined.tx_hash.parse::<H256>()?;
+        match self.client.get_transaction_receipt(tx_hash).await? {
+            Some(receipt) if !succeeded(&receipt) => return self.reverted(&mined, &receipt).await,
+            Some(_) => {}
+            None => return Ok(Progress::Pending),
+        }
+
+        let stmt = format!(
+            "update {} set status = $1, l1_tx_hash = $2 where block_id between $3 and $4",
+            models::tablenames::L2_BLOCK
+        );
+        sqlx::query(&stmt)
+            .bind(models::l2_block::BlockStatus::Commited)
+            .bind(&mined.tx_hash)
+            .bind(mined.block_id)
+            .bind(mined.last_block_id())
+            .execute(&self.connpool)
+            .await?;
+        storage::transition(&self.connpool, mined.block_range(), SubmissionStatus::Committed).await?;
+
+        let receipt = match self.wait_confirmations(tx_hash).await? {
+            Some(receipt) => receipt,
+            None => return Ok(Progress::Pending),
+        };
+        // it may have been mined again in another block after a reorg
+        if !succeeded(&receipt) {
+            return self.reverted(&mined, &receipt).await;
+        }
+        log::info!(
+            "blocks {}..={} confirmed. receipt: {:?}.",
+            mined.block_id,
+            mined.last_block_id(),
+            receipt
+        );
+
+        ledger::settle(&self.connpool, &mined, &receipt, L1TxStatus::Mined).await?;
+        let stmt = format!(
+            "update {} set status = $1, l1_tx_hash = $2 where block_id between $3 and $4",
+            models::tablenames::L2_BLOCK
+        );
+        sqlx::query(&stmt)
+            .bind(models::l2_block::BlockStatus::Verified)
+            .bind(&mined.tx_hash)
+            .bind(mined.block_id)
+            .bind(mined.last_block_id())
+            .execute(&self.connpool)
+            .await?;
+        storage::set_progress(&self.connpool, Cursor::Confirmed, mined.last_block_id()).await?;
+        storage::transition(&self.connpool, mined.block_range(), SubmissionStatus::Verified).await?;
+        Ok(Progress::Confirmed)
+    }
+
+    /// Puts blocks whose transaction reverted on-chain back up for submission.
+    async fn reverted(&self, mined: &L1PendingTx, receipt: &TransactionReceipt) -> Result<Progress, anyhow::Error> {
+        // replay it on top of the block before the one it was mined in to get the reason
+        let reason = match (&mined.tx_request, receipt.block_number) {
+            (Some(request), Some(mined_at)) => {
+                let tx: TypedTransaction = serde_json::from_value(request.clone())?;
+                let parent = BlockNumber::Number(mined_at.saturating_sub(1.into()));
+                simulation::simulate_at(&self.client, &self.revert_decoder, &tx, parent)
+                    .await
+                    .unwrap_or_else(|e| {
+                        log::warn!("replay reverted tx {}: {:?}", mined.tx_hash, e);
+                        None
+                    })
+            }
+            _ => None,
+        }
+        .unwrap_or_else(|| "reverted on-chain without a reason".to_string());
+
+        ledger::settle(&self.connpool, mined, receipt, L1TxStatus::Failed).await?;
+        simulation::record_revert(&self.connpool, (mined.block_id, mined.block_count as usize), &reason).await?;
+        let stmt = format!(
+            "update {} set status = $1, l1_tx_hash = NULL where block_id between $2 and $3",
+            models::tablenames::L2_BLOCK
+        );
+        sqlx::query(&stmt)
+            .bind(models::l2_block::BlockStatus::Uncommited)
+            .bind(mined.block_id)
+            .bind(mined.last_block_id())
+            .execute(&self.connpool)
+            .await?;
+        storage::rewind_progress(&self.connpool, mined.block_id).await?;
+        storage::transition(&self.connpool, mined.block_range(), SubmissionStatus::Uncommitted).await?;
+        Ok(Progress::Abandoned(anyhow!(
+            "blocks {}..={} reverted in tx {}: {}",
+            mined.block_id,
+            mined.last_block_id(),
+            mined.tx_hash,
+            reason
+        )))
+    }
+
+    /// Waits on the L1 heads until `tx_hash` has `confirmations`, returns `None` if it left the chain meanwhile.
+    async fn wait_confirmations(&self, tx_hash: H256) -> Result<Option<TransactionReceipt>, anyhow::Error> {
+        let mut heads = self.heads.clone();
+        let mut head = *heads.borrow();
+        loop {
+            let receipt = match self.client.get_transaction_receipt(tx_hash).await? {
+                Some(receipt) => receipt,
+                None => return Ok(None),
+            };
+            let mined_at = receipt
+                .block_number
+                .ok_or_else(|| anyhow!("receipt of tx {:#x} has no block number", tx_hash))?
+                .as_u64();
+            if head + 1 >= mined_at + self.confirmations as u64 {
+                return Ok(Some(receipt));
+            }
+            head = if next_head(&mut heads).await {
+                *heads.borrow()
+            } else {
+                // the follower is stuck, e.g. on a subscription that went quiet
+                self.client.get_block_number().await?.as_u64().max(head)
+            };
+        }
+    }
+}
+
+/// Waits for a new L1 head, for at most `RECEIPT_POLL_INTERVAL` in case the heads stall.
+/// Returns whether one came.
+async fn next_head(heads: &mut watch::Receiver<u64>) -> bool {
+    match tokio::time::timeout(RECEIPT_POLL_INTERVAL, heads.changed()).await {
+        Ok(Ok(())) => true,
+        Ok(Err(_)) => {
+            // the follower is gone, fall back to plain polling
+            tokio::time::sleep(RECEIPT_POLL_INTERVAL).await;
+            false
+        }
+        Err(_) => false,
+    }
+}
+
+/// Whether a mined transaction succeeded, a reverted one has status 0.
+fn succeeded(receipt: &TransactionReceipt) -> bool {
+    receipt.status == Some(1.into())
 }
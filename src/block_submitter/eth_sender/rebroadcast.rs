@@ -0,0 +1,140 @@
+use crate::block_submitter::config::RebroadcastSettings;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::U256;
+use std::time::Duration;
+
+/// Nodes reject a replacement paying less than this much more than the transaction it replaces.
+const MIN_BUMP_PERCENT: u64 = 10;
+
+#[derive(Debug, Clone)]
+pub struct RebroadcastPolicy {
+    pub timeout: Duration,
+    bump_percent: u64,
+    max_gas_price: U256,
+}
+
+impl RebroadcastPolicy {
+    pub fn from_config(config: &RebroadcastSettings) -> Self {
+        Self {
+            timeout: config.timeout(),
+            bump_percent: config.gas_bump_percent,
+            max_gas_price: U256::from(config.max_gas_price) * U256::exp10(9),
+        }
+    }
+
+    /// `price` bumped and capped at the ceiling, or `None` if that is too little for a replacement.
+    fn bumped(&self, price: U256) -> Option<U256> {
+        let percent = self.bump_percent.max(MIN_BUMP_PERCENT);
+        let bumped = ((price * (100 + percent) + 99) / 100).min(self.max_gas_price);
+        let min = (price * (100 + MIN_BUMP_PERCENT) + 99) / 100;
+        if bumped < min {
+            return None;
+        }
+        Some(bumped)
+    }
+
+    /// Returns a copy of `tx` with bumped fees, or `None` once the ceiling leaves no room for a replacement.
+    pub fn bump(&self, tx: &TypedTransaction) -> Option<TypedTransaction> {
+        let mut tx = tx.clone();
+        match tx {
+            TypedTransaction::Legacy(ref mut inner) => inner.gas_price = Some(self.bumped(inner.gas_price?)?),
+            TypedTransaction::Eip2930(ref mut inner) => inner.tx.gas_price = Some(self.bumped(inner.tx.gas_price?)?),
+            TypedTransaction::Eip1559(ref mut inner) => {
+                let max_fee = self.bumped(inner.max_fee_per_gas?)?;
+                inner.max_fee_per_gas = Some(max_fee);
+                // both fees have to go up by the minimum
+                if let Some(priority_fee) = inner.max_priority_fee_per_gas {
+                    inner.max_priority_fee_per_gas = Some(self.bumped(priority_fee.min(max_fee))?.min(max_fee));
+                }
+            }
+        };
+        Some(tx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::{Eip1559TransactionRequest, TransactionRequest};
+
+    fn policy(bump_percent: u64, max_gas_price: u64) -> RebroadcastPolicy {
+        RebroadcastPolicy {
+            timeout: Duration::from_secs(180),
+            bump_percent,
+            max_gas_price: max_gas_price.into(),
+        }
+    }
+
+    fn legacy(gas_price: u64) -> TypedTransaction {
+        TransactionRequest::new().gas_price(gas_price).into()
+    }
+
+    fn eip1559(max_fee: u64, priority_fee: u64) -> TypedTransaction {
+        Eip1559TransactionRequest::new()
+            .max_fee_per_gas(max_fee)
+            .max_priority_fee_per_gas(priority_fee)
+            .into()
+    }
+
+    fn gas_price(tx: &TypedTransaction) -> Option<U256> {
+        match tx {
+            TypedTransaction::Legacy(inner) => inner.gas_price,
+            _ => unreachable!(),
+        }
+    }
+
+    fn fees(tx: &TypedTransaction) -> (Option<U256>, Option<U256>) {
+        match tx {
+            TypedTransaction::Eip1559(inner) => (inner.max_fee_per_gas, inner.max_priority_fee_per_gas),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn bumps_legacy_gas_price() {
+        let bumped = policy(12, 1000).bump(&legacy(100)).unwrap();
+        assert_eq!(gas_price(&bumped), Some(112.into()));
+    }
+
+    #[test]
+    fn caps_at_max_gas_price() {
+        let bumped = policy(50, 120).bump(&legacy(100)).unwrap();
+        assert_eq!(gas_price(&bumped), Some(120.into()));
+    }
+
+    #[test]
+    fn stops_when_cap_leaves_less_than_min_bump() {
+        assert!(policy(12, 105).bump(&legacy(100)).is_none());
+        assert!(policy(12, 100).bump(&legacy(100)).is_none());
+    }
+
+    #[test]
+    fn bumps_at_least_by_min() {
+        let bumped = policy(5, 1000).bump(&legacy(100)).unwrap();
+        assert_eq!(gas_price(&bumped), Some(110.into()));
+    }
+
+    #[test]
+    fn min_bump_rounds_up() {
+        // 10% of 105 is 10.5, so a ceiling of 115 is not enough
+        assert!(policy(10, 115).bump(&legacy(105)).is_none());
+        assert_eq!(gas_price(&policy(10, 116).bump(&legacy(105)).unwrap()), Some(116.into()));
+    }
+
+    #[test]
+    fn bumps_both_eip1559_fees() {
+        let bumped = policy(12, 1000).bump(&eip1559(200, 10)).unwrap();
+        assert_eq!(fees(&bumped), (Some(224.into()), Some(12.into())));
+    }
+
+    #[test]
+    fn keeps_priority_fee_under_max_fee() {
+        let bumped = policy(12, 110).bump(&eip1559(100, 100)).unwrap();
+        assert_eq!(fees(&bumped), (Some(110.into()), Some(110.into())));
+    }
+
+    #[test]
+    fn stops_when_eip1559_max_fee_is_capped() {
+        assert!(policy(12, 105).bump(&eip1559(100, 10)).is_none());
+    }
+}
@@ -0,0 +1,72 @@
+//! Pre-flight `eth_call` of a transaction against the pending block, so that a revert
+//! is caught, decoded and recorded before any gas is spent.
+
+use crate::block_submitter::storage::models;
+use crate::contracts::RevertDecoder;
+use crate::l1::transport::{L1Provider, MultiTransportError};
+use crate::storage::PoolType;
+use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::utils;
+
+/// Returns the decoded revert reason if `tx` would revert.
+pub async fn simulate(provider: &L1Provider, decoder: &RevertDecoder, tx: &TypedTransaction) -> Result<Option<String>, anyhow::Error> {
+    simulate_at(provider, decoder, tx, BlockNumber::Pending).await
+}
+
+/// Returns the decoded revert reason if `tx` reverts on top of `block`.
+pub async fn simulate_at(
+    provider: &L1Provider,
+    decoder: &RevertDecoder,
+    tx: &TypedTransaction,
+    block: BlockNumber,
+) -> Result<Option<String>, anyhow::Error> {
+    let params = [utils::serialize(tx), utils::serialize(&block)];
+    let err = match provider.request::<_, Bytes>("eth_call", params).await {
+        Ok(_) => return Ok(None),
+        Err(err) => err,
+    };
+
+    let rpc_err = match &err {
+        ProviderError::JsonRpcClientError(inner) => match inner.downcast_ref::<MultiTransportError>() {
+            Some(MultiTransportError::JsonRpc(rpc_err)) => rpc_err,
+            _ => return Err(err.into()),
+        },
+        _ => return Err(err.into()),
+    };
+
+    Ok(Some(match rpc_err.data.as_ref().and_then(revert_data) {
+        Some(data) => decoder.decode(&data),
+        None => rpc_err.message.clone(),
+    }))
+}
+
+/// Finds the revert data in the `data` of a JSON-RPC error. Geth puts it there as a hex string,
+/// ganache nests it in an object keyed by transaction hash.
+fn revert_data(data: &serde_json::Value) -> Option<Vec<u8>> {
+    match data {
+        serde_json::Value::String(s) => s.strip_prefix("0x").and_then(|s| hex::decode(s).ok()),
+        serde_json::Value::Object(map) => map
+            .get("return")
+            .and_then(revert_data)
+            .or_else(|| map.values().find_map(revert_data)),
+        _ => None,
+    }
+}
+
+pub async fn record_revert(connpool: &PoolType, (block_id, block_count): (i64, usize), reason: &str) -> Result<(), anyhow::Error> {
+    let stmt = format!(
+        "insert into {} (block_id, revert_reason, reverted_time)
+        select id, $3, CURRENT_TIMESTAMP from generate_series($1::bigint, $2::bigint) as id
+        on conflict (block_id) do update set revert_reason = excluded.revert_reason,
+            reverted_time = excluded.reverted_time, updated_time = CURRENT_TIMESTAMP",
+        models::tablenames::BLOCK_SUBMISSION
+    );
+    sqlx::query(&stmt)
+        .bind(block_id)
+        .bind(block_id + block_count as i64 - 1)
+        .bind(reason)
+        .execute(connpool)
+        .await?;
+    Ok(())
+}
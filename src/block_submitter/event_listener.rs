@@ -0,0 +1,150 @@
+use crate::block_submitter::config::{EventListenerMode, EventListenerSettings};
+use crate::block_submitter::storage::{self, models::SubmissionStatus};
+use crate::block_submitter::Settings;
+use crate::contracts;
+use crate::l1::{heads, transport, L1Provider};
+use crate::storage::PoolType;
+use anyhow::anyhow;
+use ethers::abi::{Event, RawLog, Token};
+use ethers::prelude::*;
+use fluidex_common::db::models;
+
+/// Syncs the rollup contract's block events into `l2_block`, so that blocks submitted by
+/// other operators or tools are reflected as well. Lags `confirmations` behind the head.
+#[derive(Debug)]
+pub struct EventListener {
+    connpool: PoolType,
+    provider: L1Provider,
+    contract_address: Address,
+    event: Event,
+    settings: EventListenerSettings,
+    confirmations: u64,
+}
+
+impl EventListener {
+    pub async fn from_config_with_pool(config: &Settings, connpool: PoolType) -> Result<Self, anyhow::Error> {
+        let abi = contracts::get_abi(&config.contract_abi_file_path)?;
+        let event = abi.event(&config.event_listener.event)?.clone();
+        let mut settings = config.event_listener.clone();
+        if settings.ws_url.is_none() && transport::is_pubsub(&config.web3_url) {
+            settings.ws_url = Some(config.web3_url.clone());
+        }
+        if settings.mode == EventListenerMode::Websocket && settings.ws_url.is_none() {
+            return Err(anyhow!(
+                "event_listener.ws_url is required in websocket mode unless web3_url is a WebSocket or IPC one"
+            ));
+        }
+
+        Ok(Self {
+            connpool,
+            provider: transport::provider(&config.web3_url, &config.rpc).await?,
+            contract_address: config.contract_address.parse::<Address>()?,
+            event,
+            settings,
+            confirmations: config.confirmations as u64,
+        })
+    }
+
+    pub async fn run(&self) {
+        match self.settings.mode {
+            EventListenerMode::Polling => self.run_polling().await,
+            EventListenerMode::Websocket => self.run_subscribed().await,
+        }
+    }
+
+    async fn run_polling(&self) {
+        let mut timer = tokio::time::interval(self.settings.poll_interval());
+        loop {
+            timer.tick().await;
+            log::debug!("ticktock!");
+
+            if let Err(e) = self.sync().await {
+                log::error!("{}", e);
+            };
+        }
+    }
+
+    async fn run_subscribed(&self) {
+        let ws_url = self.settings.ws_url.clone().unwrap();
+        let mut heads = heads::watch(ws_url, self.provider.clone(), self.settings.poll_interval());
+        while heads.changed().await.is_ok() {
+            if let Err(e) = self.sync().await {
+                log::error!("{}", e);
+            };
+        }
+    }
+
+    /// Fetches and applies the events of the L1 blocks since the last sync.
+    async fn sync(&self) -> Result<(), anyhow::Error> {
+        let progress = storage::load_progress(&self.connpool).await?;
+        let from = match progress.last_synced_l1_block {
+            Some(synced) => synced as u64 + 1,
+            None => self.settings.start_block,
+        };
+        let head = self.provider.get_block_number().await?.as_u64();
+        let to = head
+            .saturating_sub(self.confirmations)
+            .min(from + self.settings.max_block_range.get() - 1);
+        if to < from {
+            return Ok(());
+        }
+
+        let filter = Filter::new()
+            .address(self.contract_address)
+            .topic0(self.event.signature())
+            .from_block(from)
+            .to_block(to);
+        let logs = self.provider.get_logs(&filter).await?;
+
+        let mut db_tx = self.connpool.begin().await?;
+        for log in logs {
+            self.apply(&mut db_tx, &log).await?;
+        }
+        storage::set_synced_l1_block(&mut db_tx, to as i64).await?;
+        db_tx.commit().await?;
+        Ok(())
+    }
+
+    async fn apply(&self, db_tx: &mut sqlx::Transaction<'_, crate::storage::DbType>, log: &Log) -> Result<(), anyhow::Error> {
+        let parsed = self.event.parse_log(RawLog {
+            topics: log.topics.clone(),
+            data: log.data.to_vec(),
+        })?;
+        let id = parsed
+            .params
+            .iter()
+            .find(|p| p.name == self.settings.block_id_param)
+            .and_then(|p| match &p.value {
+                Token::Uint(id) => Some(*id),
+                _ => None,
+            })
+            .ok_or_else(|| anyhow!("{} event without {}", self.event.name, self.settings.block_id_param))?;
+        let block_id = u64::try_from(id).ok().and_then(|id| i64::try_from(id).ok()).ok_or_else(|| {
+            anyhow!(
+                "{} event with {} {} out of range",
+                self.event.name,
+                self.settings.block_id_param,
+                id
+            )
+        })?;
+        let tx_hash = log.transaction_hash.map(|h| format!("{:#x}", h));
+
+        let stmt = format!(
+            "update {} set status = $1, l1_tx_hash = $2
+            where block_id = $3 and (status <> $1 or l1_tx_hash is distinct from $2)",
+            models::tablenames::L2_BLOCK
+        );
+        let res = sqlx::query(&stmt)
+            .bind(models::l2_block::BlockStatus::Verified)
+            .bind(&tx_hash)
+            .bind(block_id)
+            .execute(&mut *db_tx)
+            .await?;
+        if res.rows_affected() > 0 {
+            log::info!("block {} submitted on-chain in tx {:?}, l2_block updated", block_id, tx_hash);
+            // the reorg watcher may have finalized it already
+            storage::advance(&mut *db_tx, (block_id, block_id), SubmissionStatus::Verified).await?;
+        }
+        Ok(())
+    }
+}
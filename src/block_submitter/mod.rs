@@ -1,9 +1,17 @@
 pub mod config;
+pub mod consistency;
 pub mod eth_sender;
+pub mod event_listener;
+pub mod proof_checker;
+pub mod reconciler;
+pub mod reorg_watcher;
 pub mod storage;
 pub mod task_fetcher;
 pub mod types;
 
 pub use config::Settings;
 pub use eth_sender::EthSender;
+pub use event_listener::EventListener;
+pub use reconciler::Reconciler;
+pub use reorg_watcher::ReorgWatcher;
 pub use task_fetcher::TaskFetcher;
@@ -0,0 +1,229 @@
+//! Sanity checks of a block's proof before it is queued for submission, so that a malformed
+//! one is held back instead of costing a reverted L1 transaction.
+
+use super::types::SubmitBlockArgs;
+use crate::block_submitter::config::{ProofSettings, VerifierSettings};
+use anyhow::anyhow;
+use ethers::types::U256;
+use std::path::Path;
+
+/// Order of the BN254 scalar field, public inputs are elements of it.
+pub const SCALAR_FIELD_MODULUS: &str = "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+/// Order of the BN254 base field, curve point coordinates in the proof are elements of it.
+const BASE_FIELD_MODULUS: &str = "21888242871839275222246405745257275088696311157297823662689037894645226208583";
+
+#[derive(Debug)]
+pub struct ProofChecker {
+    public_inputs_len: Option<usize>,
+    proof_len: Option<usize>,
+    verifier: Option<VerifierSettings>,
+    scalar_field_modulus: U256,
+    base_field_modulus: U256,
+}
+
+impl ProofChecker {
+    pub fn from_config(config: &ProofSettings) -> Self {
+        Self {
+            public_inputs_len: config.public_inputs_len,
+            proof_len: config.proof_len,
+            verifier: config.verifier.clone(),
+            scalar_field_modulus: U256::from_dec_str(SCALAR_FIELD_MODULUS).unwrap(),
+            base_field_modulus: U256::from_dec_str(BASE_FIELD_MODULUS).unwrap(),
+        }
+    }
+
+    /// Returns why `args` would be refused by the verifier contract, if it would.
+    pub async fn check(&self, args: &SubmitBlockArgs) -> Result<Option<String>, anyhow::Error> {
+        if let Some(expected) = self.public_inputs_len {
+            if args.public_inputs.len() != expected {
+                return Ok(Some(format!("{} public inputs, expected {}", args.public_inputs.len(), expected)));
+            }
+        }
+        if let Some(expected) = self.proof_len {
+            if args.serialized_proof.len() != expected {
+                return Ok(Some(format!(
+                    "{} proof elements, expected {}",
+                    args.serialized_proof.len(),
+                    expected
+                )));
+            }
+        }
+        if let Some(i) = args.public_inputs.iter().position(|x| *x >= self.scalar_field_modulus) {
+            return Ok(Some(format!("public input {} is not in the scalar field", i)));
+        }
+        if let Some(i) = args.serialized_proof.iter().position(|x| *x >= self.base_field_modulus) {
+            return Ok(Some(format!("proof element {} is not in the base field", i)));
+        }
+
+        match &self.verifier {
+            Some(verifier) => verify(verifier, args).await,
+            None => Ok(None),
+        }
+    }
+}
+
+async fn write_elements(path: &Path, elements: &[U256]) -> Result<(), anyhow::Error> {
+    let elements: Vec<String> = elements.iter().map(|x| x.to_string()).collect();
+    tokio::fs::write(path, serde_json::to_vec(&elements)?).await?;
+    Ok(())
+}
+
+/// Runs the external verifier, an error means it could not be run rather than a bad proof.
+async fn verify(verifier: &VerifierSettings, args: &SubmitBlockArgs) -> Result<Option<String>, anyhow::Error> {
+    // a fresh directory only this user can read, so the inputs cannot be swapped underneath the verifier
+    let prefix = format!("block_submitter_{}_", args.block_id);
+    let dir = tokio::task::spawn_blocking(move || tempfile::Builder::new().prefix(&prefix).tempdir()).await??;
+    let public_inputs_path = dir.path().join("public_inputs.json");
+    let proof_path = dir.path().join("proof.json");
+    write_elements(&public_inputs_path, &args.public_inputs).await?;
+    write_elements(&proof_path, &args.serialized_proof).await?;
+
+    let cmd_args: Vec<String> = verifier
+        .args
+        .iter()
+        .map(|arg| {
+            arg.replace("{vk}", &verifier.vk_path)
+                .replace("{public_inputs}", &public_inputs_path.to_string_lossy())
+                .replace("{proof}", &proof_path.to_string_lossy())
+        })
+        .collect();
+    // a hung verifier would hold up the fetcher, killed once the timeout drops it
+    let output = tokio::time::timeout(
+        verifier.timeout(),
+        tokio::process::Command::new(&verifier.command)
+            .args(&cmd_args)
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await;
+
+    if let Err(e) = tokio::task::spawn_blocking(move || dir.close()).await? {
+        log::warn!("remove verifier inputs: {}", e);
+    }
+
+    let output = match output {
+        Ok(output) => output.map_err(|e| anyhow!("run verifier {}: {}", verifier.command, e))?,
+        Err(_) => return Ok(Some(format!("verifier timed out after {}s", verifier.timeout))),
+    };
+    if output.status.success() {
+        return Ok(None);
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(Some(format!(
+        "rejected by verifier ({}): {}",
+        output.status,
+        if stderr.trim().is_empty() { stdout.trim() } else { stderr.trim() }
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checker(verifier: Option<VerifierSettings>) -> ProofChecker {
+        ProofChecker::from_config(&ProofSettings {
+            public_inputs_len: Some(1),
+            proof_len: Some(2),
+            verifier,
+        })
+    }
+
+    fn args(public_inputs: Vec<U256>, serialized_proof: Vec<U256>) -> SubmitBlockArgs {
+        SubmitBlockArgs {
+            block_id: 7.into(),
+            public_inputs,
+            serialized_proof,
+        }
+    }
+
+    fn sh(script: &str) -> Option<VerifierSettings> {
+        Some(VerifierSettings {
+            command: "sh".to_string(),
+            args: vec!["-c".to_string(), script.to_string()],
+            vk_path: "vk.json".to_string(),
+            timeout: 5,
+        })
+    }
+
+    #[tokio::test]
+    async fn accepts_well_formed_proof() {
+        let res = checker(None).check(&args(vec![1.into()], vec![2.into(), 3.into()])).await.unwrap();
+        assert_eq!(res, None);
+    }
+
+    #[tokio::test]
+    async fn rejects_wrong_lengths() {
+        let res = checker(None).check(&args(vec![], vec![2.into(), 3.into()])).await.unwrap();
+        assert_eq!(res.as_deref(), Some("0 public inputs, expected 1"));
+        let res = checker(None).check(&args(vec![1.into()], vec![2.into()])).await.unwrap();
+        assert_eq!(res.as_deref(), Some("1 proof elements, expected 2"));
+    }
+
+    #[tokio::test]
+    async fn rejects_elements_outside_their_field() {
+        let scalar_modulus = U256::from_dec_str(SCALAR_FIELD_MODULUS).unwrap();
+        let res = checker(None)
+            .check(&args(vec![scalar_modulus], vec![2.into(), 3.into()]))
+            .await
+            .unwrap();
+        assert_eq!(res.as_deref(), Some("public input 0 is not in the scalar field"));
+
+        // below the base field modulus, which is the larger one
+        let res = checker(None)
+            .check(&args(vec![1.into()], vec![2.into(), scalar_modulus]))
+            .await
+            .unwrap();
+        assert_eq!(res, None);
+        let base_modulus = U256::from_dec_str(BASE_FIELD_MODULUS).unwrap();
+        let res = checker(None)
+            .check(&args(vec![1.into()], vec![2.into(), base_modulus]))
+            .await
+            .unwrap();
+        assert_eq!(res.as_deref(), Some("proof element 1 is not in the base field"));
+    }
+
+    #[tokio::test]
+    async fn passes_inputs_to_verifier() {
+        let verifier = sh(r#"test "$(cat {public_inputs})" = '["1"]' && test "$(cat {proof})" = '["2","3"]' && test {vk} = vk.json"#);
+        let res = checker(verifier)
+            .check(&args(vec![1.into()], vec![2.into(), 3.into()]))
+            .await
+            .unwrap();
+        assert_eq!(res, None);
+    }
+
+    #[tokio::test]
+    async fn reports_verifier_rejection() {
+        let verifier = sh("echo bad proof >&2; exit 1");
+        let res = checker(verifier)
+            .check(&args(vec![1.into()], vec![2.into(), 3.into()]))
+            .await
+            .unwrap();
+        assert_eq!(res.as_deref(), Some("rejected by verifier (exit status: 1): bad proof"));
+    }
+
+    #[tokio::test]
+    async fn fails_when_verifier_cannot_run() {
+        let verifier = Some(VerifierSettings {
+            command: "/nonexistent/verifier".to_string(),
+            args: vec![],
+            vk_path: "vk.json".to_string(),
+            timeout: 5,
+        });
+        assert!(checker(verifier)
+            .check(&args(vec![1.into()], vec![2.into(), 3.into()]))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn rejects_when_verifier_times_out() {
+        let verifier = sh("sleep 10").map(|verifier| VerifierSettings { timeout: 1, ..verifier });
+        let res = checker(verifier)
+            .check(&args(vec![1.into()], vec![2.into(), 3.into()]))
+            .await
+            .unwrap();
+        assert_eq!(res.as_deref(), Some("verifier timed out after 1s"));
+    }
+}
@@ -0,0 +1,236 @@
+use super::consistency;
+use super::types::BlockState;
+use crate::block_submitter::eth_sender::ledger;
+use crate::block_submitter::storage::{
+    self,
+    models::{Cursor, SubmissionStatus},
+};
+use crate::block_submitter::Settings;
+use crate::contracts;
+use crate::l1::{transport, L1Provider};
+use crate::storage::PoolType;
+use anyhow::anyhow;
+use ethers::prelude::*;
+use fluidex_common::db::models;
+use std::time::Duration;
+
+/// Keeps `l2_block` in line with the rollup contract, which is the source of truth for what
+/// has been submitted, no matter whether it was us, another operator or a manual call.
+#[derive(Debug)]
+pub struct Reconciler {
+    connpool: PoolType,
+    provider: L1Provider,
+    contract: Contract<L1Provider>,
+    confirmations: u64,
+    state_root_method: Option<String>,
+    interval: Duration,
+}
+
+impl Reconciler {
+    pub async fn from_config_with_pool(config: &Settings, connpool: PoolType) -> Result<Self, anyhow::Error> {
+        let address = config.contract_address.parse::<Address>()?;
+        let abi = contracts::get_abi(&config.contract_abi_file_path)?;
+        let provider = transport::provider(&config.web3_url, &config.rpc).await?;
+
+        Ok(Self {
+            connpool,
+            contract: Contract::new(address, abi, provider.clone()),
+            provider,
+            confirmations: config.confirmations as u64,
+            state_root_method: config.reconcile.state_root_method.clone(),
+            interval: config.reconcile.interval(),
+        })
+    }
+
+    /// Reconciles once at startup, including the submitter's own cursor, before anything is fetched.
+    pub async fn startup(&self) -> Result<(), anyhow::Error> {
+        // nothing is being submitted yet, so whatever is missing at the head can be re-queued
+        let chain_last = self.reconcile(BlockNumber::Latest, i64::MAX).await?;
+
+        let progress = storage::load_progress(&self.connpool).await?;
+        let pending = ledger::max_pending_block_id(&self.connpool).await?;
+        // blocks with a transaction in the ledger are resumed by `EthSender`, whatever was fetched
+        // beyond them never reached the chain and has to be fetched again
+        let sent = pending.map_or(chain_last, |block_id| block_id.max(chain_last));
+        let mut db_tx = self.connpool.begin().await?;
+        storage::set_progress(&mut db_tx, Cursor::Confirmed, chain_last).await?;
+        storage::set_progress(&mut db_tx, Cursor::Sent, sent).await?;
+        storage::set_progress(&mut db_tx, Cursor::Fetched, sent).await?;
+        db_tx.commit().await?;
+
+        log::info!(
+            "block submitter progress reconciled, last block on-chain: {} (was {:?})",
+            chain_last,
+            progress
+        );
+        Ok(())
+    }
+
+    /// Reconciles on a timer, only returns when the submitter has to stop.
+    pub async fn run(&self) -> Result<(), anyhow::Error> {
+        let mut timer = tokio::time::interval(self.interval);
+        loop {
+            timer.tick().await;
+            log::debug!("ticktock!");
+
+            match self.reconcile_settled().await {
+                Err(e) if e.is::<ContractAhead>() => return Err(e),
+                Err(e) => log::error!("{}", e),
+                Ok(_) => {}
+            }
+        }
+    }
+
+    /// Reconciles with the contract `confirmations` behind the head, a read all endpoints agree on
+    /// when there is a quorum. Only blocks `EthSender` has confirmed are put back up for
+    /// submission, and none whose transaction got mined after the block read at.
+    async fn reconcile_settled(&self) -> Result<i64, anyhow::Error> {
+        let head = self.provider.get_block_number().await?.as_u64();
+        let settled = head.saturating_sub(self.confirmations);
+
+        let progress = storage::load_progress(&self.connpool).await?;
+        let mut uncommit_last = progress.last_confirmed_block_id;
+        if let Some(recent) = ledger::first_block_mined_after(&self.connpool, settled as i64).await? {
+            uncommit_last = uncommit_last.min(recent - 1);
+        }
+        self.reconcile(BlockNumber::Number(settled.into()), uncommit_last).await
+    }
+
+    /// Corrects `l2_block` where it disagrees with the contract as of block `at`, marking blocks
+    /// uncommited up to `uncommit_last` at most. Returns the last block on-chain.
+    async fn reconcile(&self, at: BlockNumber, uncommit_last: i64) -> Result<i64, anyhow::Error> {
+        let query = format!(
+            "select coalesce(max(block_id), -1) from {} where status <> 'uncommited'",
+            models::tablenames::L2_BLOCK
+        );
+        let db_last: i64 = sqlx::query_scalar(&query).fetch_one(&self.connpool).await?;
+
+        let mut chain_last = db_last;
+        while chain_last >= 0 && self.block_state(chain_last, at).await? == BlockState::Empty {
+            chain_last -= 1;
+        }
+        // states of the blocks on-chain past `db_last`, if any
+        let mut found = Vec::new();
+        loop {
+            match self.block_state(chain_last + 1, at).await? {
+                BlockState::Empty => break,
+                state => found.push(state),
+            }
+            chain_last += 1;
+        }
+
+        let query = format!(
+            "select coalesce(max(block_id), -1) from {} where status = 'proved'",
+            models::tablenames::TASK
+        );
+        let proved_last: i64 = sqlx::query_scalar(&query).fetch_one(&self.connpool).await?;
+        if chain_last > proved_last {
+            return Err(ContractAhead { chain_last, proved_last }.into());
+        }
+
+        let missing_last = db_last.min(uncommit_last);
+        if chain_last < missing_last {
+            log::warn!(
+                "blocks {}..={} are not on-chain, marking them uncommited",
+                chain_last + 1,
+                missing_last
+            );
+            let mut db_tx = self.connpool.begin().await?;
+            let stmt = format!(
+                "update {} set status = 'uncommited', l1_tx_hash = null where block_id between $1 and $2",
+                models::tablenames::L2_BLOCK
+            );
+            sqlx::query(&stmt)
+                .bind(chain_last + 1)
+                .bind(missing_last)
+                .execute(&mut db_tx)
+                .await?;
+            // whatever `EthSender` has in flight beyond them reverts and is re-queued by it
+            storage::rewind_progress(&mut db_tx, chain_last + 1).await?;
+            storage::transition(&mut db_tx, (chain_last + 1, missing_last), SubmissionStatus::Uncommitted).await?;
+            db_tx.commit().await?;
+        } else if chain_last > db_last {
+            for (block_id, state) in (db_last + 1..=chain_last).zip(found) {
+                self.mark_on_chain(block_id, state).await?;
+            }
+        }
+
+        if chain_last >= 0 {
+            self.check_state_root(chain_last, at).await?;
+        }
+        Ok(chain_last)
+    }
+
+    /// Records a block found on-chain but not in `l2_block` as committed or verified, as the
+    /// contract has it. Its `l1_tx_hash` is left to the `EventListener`, which fills it in from
+    /// the block's event.
+    async fn mark_on_chain(&self, block_id: i64, state: BlockState) -> Result<(), anyhow::Error> {
+        let (block_status, submission_status) = match state {
+            BlockState::Committed => (models::l2_block::BlockStatus::Commited, SubmissionStatus::Committed),
+            BlockState::Verified => (models::l2_block::BlockStatus::Verified, SubmissionStatus::Verified),
+            BlockState::Empty => return Ok(()),
+        };
+        log::warn!("block {} found on-chain but not recorded, marking it {:?}", block_id, state);
+        let stmt = format!("update {} set status = $1 where block_id = $2", models::tablenames::L2_BLOCK);
+        sqlx::query(&stmt).bind(block_status).bind(block_id).execute(&self.connpool).await?;
+        storage::transition(&self.connpool, (block_id, block_id), submission_status).await?;
+        Ok(())
+    }
+
+    async fn block_state(&self, block_id: i64, at: BlockNumber) -> Result<BlockState, anyhow::Error> {
+        let state = self
+            .contract
+            .method::<_, u8>("getBlockStateByBlockId", U256::from(block_id))?
+            .block(at)
+            .call()
+            .await?;
+        Ok(state.into())
+    }
+
+    /// Compares the state root the contract holds for `block_id` with `l2_block.new_root`,
+    /// if the contract exposes one through `state_root_method`.
+    async fn check_state_root(&self, block_id: i64, at: BlockNumber) -> Result<(), anyhow::Error> {
+        let method = match &self.state_root_method {
+            Some(method) => method,
+            None => return Ok(()),
+        };
+        let chain_root = self
+            .contract
+            .method::<_, U256>(method, U256::from(block_id))?
+            .block(at)
+            .call()
+            .await?;
+
+        let query = format!("select new_root from {} where block_id = $1", models::tablenames::L2_BLOCK);
+        let db_root: String = sqlx::query_scalar(&query).bind(block_id).fetch_one(&self.connpool).await?;
+        let db_root = consistency::parse_field_element(&db_root)?;
+
+        if chain_root != db_root {
+            return Err(anyhow!(
+                "state root of block {} differs, contract: {:#x}, l2_block: {:#x}",
+                block_id,
+                chain_root,
+                db_root
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct ContractAhead {
+    chain_last: i64,
+    proved_last: i64,
+}
+
+impl std::fmt::Display for ContractAhead {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "contract has block {} but the last proved block is {}, refusing to run",
+            self.chain_last, self.proved_last
+        )
+    }
+}
+
+impl std::error::Error for ContractAhead {}
@@ -0,0 +1,100 @@
+use crate::block_submitter::eth_sender::ledger;
+use crate::block_submitter::storage::{
+    self,
+    models::{self as submitter_models, L1PendingTx, L1TxStatus, SubmissionStatus},
+};
+use crate::block_submitter::Settings;
+use crate::l1::{transport, L1Provider};
+use crate::storage::PoolType;
+use ethers::prelude::*;
+use fluidex_common::db::models;
+
+/// Follows mined submissions until they are `finality_depth` blocks deep, and re-queues
+/// their blocks if a reorg takes them off the canonical chain before that.
+#[derive(Debug)]
+pub struct ReorgWatcher {
+    connpool: PoolType,
+    provider: L1Provider,
+    finality_depth: u64,
+    check_interval: std::time::Duration,
+}
+
+impl ReorgWatcher {
+    pub async fn from_config_with_pool(config: &Settings, connpool: PoolType) -> Result<Self, anyhow::Error> {
+        Ok(Self {
+            connpool,
+            provider: transport::provider(&config.web3_url, &config.rpc).await?,
+            finality_depth: config.reorg.finality_depth,
+            check_interval: config.reorg.check_interval(),
+        })
+    }
+
+    pub async fn run(&self) {
+        let mut timer = tokio::time::interval(self.check_interval);
+        loop {
+            timer.tick().await;
+            log::debug!("ticktock!");
+
+            if let Err(e) = self.run_inner().await {
+                log::error!("{}", e);
+            };
+        }
+    }
+
+    async fn run_inner(&self) -> Result<(), anyhow::Error> {
+        let head = self.provider.get_block_number().await?.as_u64();
+        for mined in ledger::load_mined(&self.connpool).await? {
+            let receipt = self.provider.get_transaction_receipt(mined.tx_hash.parse::<H256>()?).await?;
+            let block_hash = receipt.as_ref().and_then(|r| r.block_hash).map(|h| format!("{:#x}", h));
+            if block_hash.is_none() || block_hash != mined.l1_block_hash {
+                log::warn!(
+                    "tx {} of blocks {}..={} left L1 block {:?} (now {:?}), re-queueing",
+                    mined.tx_hash,
+                    mined.block_id,
+                    mined.last_block_id(),
+                    mined.l1_block_hash,
+                    block_hash
+                );
+                self.roll_back(&mined).await?;
+                continue;
+            }
+
+            let depth = head.saturating_sub(mined.l1_block_number.unwrap_or_default() as u64);
+            if depth >= self.finality_depth {
+                ledger::set_status(&self.connpool, mined.id, L1TxStatus::Finalized).await?;
+                storage::transition(&self.connpool, mined.block_range(), SubmissionStatus::Finalized).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn roll_back(&self, mined: &L1PendingTx) -> Result<(), anyhow::Error> {
+        let mut db_tx = self.connpool.begin().await?;
+
+        let stmt = format!(
+            "update {} set status = $1, updated_time = CURRENT_TIMESTAMP where id = $2",
+            submitter_models::tablenames::L1_PENDING_TX
+        );
+        sqlx::query(&stmt)
+            .bind(L1TxStatus::Reorged)
+            .bind(mined.id)
+            .execute(&mut db_tx)
+            .await?;
+
+        let stmt = format!(
+            "update {} set status = 'uncommited', l1_tx_hash = null where block_id between $1 and $2",
+            models::tablenames::L2_BLOCK
+        );
+        sqlx::query(&stmt)
+            .bind(mined.block_id)
+            .bind(mined.last_block_id())
+            .execute(&mut db_tx)
+            .await?;
+
+        storage::rewind_progress(&mut db_tx, mined.block_id).await?;
+        storage::transition(&mut db_tx, mined.block_range(), SubmissionStatus::Uncommitted).await?;
+        db_tx.commit().await?;
+        Ok(())
+    }
+}
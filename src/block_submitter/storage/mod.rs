@@ -0,0 +1,193 @@
+use crate::block_submitter::Settings;
+use crate::storage::{run_migrations, PoolOptions, PoolType};
+
+pub mod models;
+
+static MIGRATOR: sqlx::migrate::Migrator = sqlx::migrate!("./migrations/block_submitter");
+
+pub async fn from_config(config: &Settings) -> anyhow::Result<PoolType> {
+    let db_pool = PoolOptions::new().connect(&config.db).await?;
+
+    run_migrations(&db_pool, &MIGRATOR, "_block_submitter_migrations").await?;
+
+    Ok(db_pool)
+}
+
+pub async fn load_progress<'e, E>(executor: E) -> Result<models::Progress, anyhow::Error>
+where
+    E: sqlx::Executor<'e, Database = crate::storage::DbType>,
+{
+    let query = format!(
+        "select last_fetched_block_id, last_sent_block_id, last_confirmed_block_id, last_synced_l1_block, updated_time
+        from {} where id = 1",
+        models::tablenames::BLOCK_SUBMITTER_PROGRESS
+    );
+    Ok(sqlx::query_as(&query).fetch_one(executor).await?)
+}
+
+/// Moves a cursor to `block_id`, or rewinds it when `block_id` is lower.
+pub async fn set_progress<'e, E>(executor: E, cursor: models::Cursor, block_id: i64) -> Result<(), anyhow::Error>
+where
+    E: sqlx::Executor<'e, Database = crate::storage::DbType>,
+{
+    let stmt = format!(
+        "update {} set {} = $1, updated_time = CURRENT_TIMESTAMP where id = 1",
+        models::tablenames::BLOCK_SUBMITTER_PROGRESS,
+        cursor.column()
+    );
+    sqlx::query(&stmt).bind(block_id).execute(executor).await?;
+    Ok(())
+}
+
+/// Rewinds the cursors so that `block_id` is fetched again.
+pub async fn rewind_progress<'e, E>(executor: E, block_id: i64) -> Result<(), anyhow::Error>
+where
+    E: sqlx::Executor<'e, Database = crate::storage::DbType>,
+{
+    let stmt = format!(
+        "update {} set last_fetched_block_id = least(last_fetched_block_id, $1),
+            last_sent_block_id = least(last_sent_block_id, $1),
+            last_confirmed_block_id = least(last_confirmed_block_id, $1),
+            updated_time = CURRENT_TIMESTAMP
+        where id = 1",
+        models::tablenames::BLOCK_SUBMITTER_PROGRESS
+    );
+    sqlx::query(&stmt).bind(block_id - 1).execute(executor).await?;
+    Ok(())
+}
+
+/// Moves blocks `first..=last` to `status` and stamps the transition time.
+pub async fn transition<'e, E>(executor: E, blocks: (i64, i64), status: models::SubmissionStatus) -> Result<(), anyhow::Error>
+where
+    E: sqlx::Executor<'e, Database = crate::storage::DbType>,
+{
+    upsert_status(executor, blocks, status, "").await
+}
+
+/// Moves blocks `first..=last` forward to `status`, leaving the ones already past it alone.
+pub async fn advance<'e, E>(executor: E, blocks: (i64, i64), status: models::SubmissionStatus) -> Result<(), anyhow::Error>
+where
+    E: sqlx::Executor<'e, Database = crate::storage::DbType>,
+{
+    let guard = format!("where {}.status < excluded.status", models::tablenames::BLOCK_SUBMISSION);
+    upsert_status(executor, blocks, status, &guard).await
+}
+
+async fn upsert_status<'e, E>(
+    executor: E,
+    (first, last): (i64, i64),
+    status: models::SubmissionStatus,
+    guard: &str,
+) -> Result<(), anyhow::Error>
+where
+    E: sqlx::Executor<'e, Database = crate::storage::DbType>,
+{
+    let (time_column, time_value) = match status.time_column() {
+        Some(column) => (format!(", {}", column), ", CURRENT_TIMESTAMP"),
+        None => (String::new(), ""),
+    };
+    let stmt = format!(
+        "insert into {table} (block_id, status{time_column})
+        select id, $3{time_value} from generate_series($1::bigint, $2::bigint) as id
+        on conflict (block_id) do update set {set} {guard}",
+        table = models::tablenames::BLOCK_SUBMISSION,
+        time_column = time_column,
+        time_value = time_value,
+        set = status.set_clause(),
+        guard = guard,
+    );
+    sqlx::query(&stmt).bind(first).bind(last).bind(status).execute(executor).await?;
+    Ok(())
+}
+
+pub async fn set_synced_l1_block<'e, E>(executor: E, l1_block: i64) -> Result<(), anyhow::Error>
+where
+    E: sqlx::Executor<'e, Database = crate::storage::DbType>,
+{
+    let stmt = format!(
+        "update {} set last_synced_l1_block = $1, updated_time = CURRENT_TIMESTAMP where id = 1",
+        models::tablenames::BLOCK_SUBMITTER_PROGRESS
+    );
+    sqlx::query(&stmt).bind(l1_block).execute(executor).await?;
+    Ok(())
+}
+
+/// Records why `block_id` is held back before submission.
+pub async fn record_rejection<'e, E>(executor: E, block_id: i64, reason: &str) -> Result<(), anyhow::Error>
+where
+    E: sqlx::Executor<'e, Database = crate::storage::DbType>,
+{
+    let stmt = format!(
+        "insert into {} (block_id, rejected_reason, rejected_time) values ($1, $2, CURRENT_TIMESTAMP)
+        on conflict (block_id) do update set rejected_reason = excluded.rejected_reason,
+            rejected_time = excluded.rejected_time, updated_time = CURRENT_TIMESTAMP",
+        models::tablenames::BLOCK_SUBMISSION
+    );
+    sqlx::query(&stmt).bind(block_id).bind(reason).execute(executor).await?;
+    Ok(())
+}
+
+/// Last block fetched in shadow mode, which keeps its own cursor. It starts after the last block
+/// the live submitter confirmed or `l2_block` has on-chain, whichever is further.
+pub async fn load_shadow_progress<'e, E>(executor: E) -> Result<i64, anyhow::Error>
+where
+    E: sqlx::Executor<'e, Database = crate::storage::DbType>,
+{
+    let query = format!(
+        "with seeded as (
+            update {shadow} set last_fetched_block_id = greatest(
+                (select last_confirmed_block_id from {live} where id = 1),
+                (select coalesce(max(block_id), -1) from {l2_block} where status <> 'uncommited')
+            ), updated_time = CURRENT_TIMESTAMP
+            where id = 1 and last_fetched_block_id is null
+            returning last_fetched_block_id
+        )
+        select last_fetched_block_id from seeded
+        union all
+        select last_fetched_block_id from {shadow} where id = 1 and last_fetched_block_id is not null",
+        shadow = models::tablenames::BLOCK_SUBMITTER_SHADOW_PROGRESS,
+        live = models::tablenames::BLOCK_SUBMITTER_PROGRESS,
+        l2_block = fluidex_common::db::models::tablenames::L2_BLOCK,
+    );
+    Ok(sqlx::query_scalar(&query).fetch_one(executor).await?)
+}
+
+pub async fn set_shadow_progress<'e, E>(executor: E, block_id: i64) -> Result<(), anyhow::Error>
+where
+    E: sqlx::Executor<'e, Database = crate::storage::DbType>,
+{
+    let stmt = format!(
+        "update {} set last_fetched_block_id = $1, updated_time = CURRENT_TIMESTAMP where id = 1",
+        models::tablenames::BLOCK_SUBMITTER_SHADOW_PROGRESS
+    );
+    sqlx::query(&stmt).bind(block_id).execute(executor).await?;
+    Ok(())
+}
+
+/// Records the shadow outcome of blocks `first..first + count`, replacing any earlier one.
+pub async fn record_shadow<'e, E>(executor: E, (first, count): (i64, usize), outcome: &models::ShadowOutcome) -> Result<(), anyhow::Error>
+where
+    E: sqlx::Executor<'e, Database = crate::storage::DbType>,
+{
+    let stmt = format!(
+        "insert into {} (block_id, batch_first_block_id, batch_block_count, tx_request, gas_limit, revert_reason, guard_breach, rejected_reason)
+        select id, $1, $2, $3, $4, $5, $6, $7 from generate_series($1::bigint, $1::bigint + $2 - 1) as id
+        on conflict (block_id) do update set batch_first_block_id = excluded.batch_first_block_id,
+            batch_block_count = excluded.batch_block_count, tx_request = excluded.tx_request,
+            gas_limit = excluded.gas_limit, revert_reason = excluded.revert_reason,
+            guard_breach = excluded.guard_breach, rejected_reason = excluded.rejected_reason,
+            updated_time = CURRENT_TIMESTAMP",
+        models::tablenames::BLOCK_SUBMISSION_SHADOW
+    );
+    sqlx::query(&stmt)
+        .bind(first)
+        .bind(count as i32)
+        .bind(&outcome.tx_request)
+        .bind(outcome.gas_limit)
+        .bind(&outcome.revert_reason)
+        .bind(&outcome.guard_breach)
+        .bind(&outcome.rejected_reason)
+        .execute(executor)
+        .await?;
+    Ok(())
+}
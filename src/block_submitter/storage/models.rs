@@ -0,0 +1,163 @@
+use crate::storage::TimestampDbType;
+use serde::Serialize;
+
+pub mod tablenames {
+    pub const BLOCK_SUBMITTER_PROGRESS: &str = "block_submitter_progress";
+    pub const L1_PENDING_TX: &str = "l1_pending_tx";
+    pub const BLOCK_SUBMISSION: &str = "block_submission";
+    pub const OPERATOR_ALERT: &str = "operator_alert";
+    pub const BLOCK_SUBMISSION_SHADOW: &str = "block_submission_shadow";
+    pub const BLOCK_SUBMITTER_SHADOW_PROGRESS: &str = "block_submitter_shadow_progress";
+}
+
+/// Single-row cursor of the block submitter, `-1` means nothing yet.
+#[derive(sqlx::FromRow, Debug, Clone, Serialize)]
+pub struct Progress {
+    pub last_fetched_block_id: i64,
+    pub last_sent_block_id: i64,
+    pub last_confirmed_block_id: i64,
+    pub last_synced_l1_block: Option<i64>,
+    pub updated_time: TimestampDbType,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Cursor {
+    Fetched,
+    Sent,
+    Confirmed,
+}
+
+impl Cursor {
+    pub fn column(&self) -> &'static str {
+        match self {
+            Cursor::Fetched => "last_fetched_block_id",
+            Cursor::Sent => "last_sent_block_id",
+            Cursor::Confirmed => "last_confirmed_block_id",
+        }
+    }
+}
+
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Serialize)]
+#[sqlx(type_name = "l1_tx_status", rename_all = "snake_case")]
+pub enum L1TxStatus {
+    Pending,
+    Mined,
+    Dropped,
+    Replaced,
+    /// Mined and buried under the configured finality depth.
+    Finalized,
+    /// Mined, then removed from the canonical chain.
+    Reorged,
+    /// Mined, but reverted.
+    Failed,
+}
+
+/// A signed L1 transaction, recorded before it is broadcast.
+#[derive(sqlx::FromRow, Debug, Clone, Serialize)]
+pub struct L1PendingTx {
+    pub id: i32,
+    pub block_id: i64,
+    pub nonce: i64,
+    pub tx_hash: String,
+    pub gas_limit: i64,
+    pub gas_price: Option<i64>,
+    pub max_fee_per_gas: Option<i64>,
+    pub max_priority_fee_per_gas: Option<i64>,
+    pub raw_tx: Vec<u8>,
+    pub tx_request: Option<serde_json::Value>,
+    pub status: L1TxStatus,
+    pub block_count: i32,
+    pub l1_block_number: Option<i64>,
+    pub l1_block_hash: Option<String>,
+    pub created_time: TimestampDbType,
+    pub updated_time: TimestampDbType,
+}
+
+impl L1PendingTx {
+    pub fn last_block_id(&self) -> i64 {
+        self.block_id + self.block_count as i64 - 1
+    }
+
+    pub fn block_range(&self) -> (i64, i64) {
+        (self.block_id, self.last_block_id())
+    }
+}
+
+/// Lifecycle of a block on L1, each status has a `<status>_time` column in `block_submission`.
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Serialize)]
+#[sqlx(type_name = "submission_status", rename_all = "snake_case")]
+pub enum SubmissionStatus {
+    Uncommitted,
+    /// Transaction broadcast.
+    Submitting,
+    /// Transaction mined.
+    Committed,
+    /// Transaction has the configured `confirmations`.
+    Verified,
+    /// Transaction is below the reorg finality depth.
+    Finalized,
+}
+
+impl SubmissionStatus {
+    /// Timestamp columns cleared when a block falls back to this status.
+    fn later_time_columns(&self) -> &'static [&'static str] {
+        match self {
+            SubmissionStatus::Uncommitted => &["submitting_time", "committed_time", "verified_time", "finalized_time"],
+            SubmissionStatus::Submitting => &["committed_time", "verified_time", "finalized_time"],
+            SubmissionStatus::Committed => &["verified_time", "finalized_time"],
+            SubmissionStatus::Verified => &["finalized_time"],
+            SubmissionStatus::Finalized => &[],
+        }
+    }
+
+    pub fn time_column(&self) -> Option<&'static str> {
+        match self {
+            SubmissionStatus::Uncommitted => None,
+            SubmissionStatus::Submitting => Some("submitting_time"),
+            SubmissionStatus::Committed => Some("committed_time"),
+            SubmissionStatus::Verified => Some("verified_time"),
+            SubmissionStatus::Finalized => Some("finalized_time"),
+        }
+    }
+
+    /// `set` clause recording a transition to this status.
+    pub fn set_clause(&self) -> String {
+        let mut assignments = vec![
+            "status = excluded.status".to_string(),
+            "updated_time = CURRENT_TIMESTAMP".to_string(),
+        ];
+        if let Some(column) = self.time_column() {
+            assignments.push(format!("{} = CURRENT_TIMESTAMP", column));
+        }
+        assignments.extend(self.later_time_columns().iter().map(|column| format!("{} = NULL", column)));
+        assignments.join(", ")
+    }
+}
+
+/// What the submitter knows about a block beyond `l2_block`.
+#[derive(sqlx::FromRow, Debug, Clone, Serialize)]
+pub struct BlockSubmission {
+    pub block_id: i64,
+    pub status: SubmissionStatus,
+    pub revert_reason: Option<String>,
+    pub reverted_time: Option<TimestampDbType>,
+    pub submitting_time: Option<TimestampDbType>,
+    pub committed_time: Option<TimestampDbType>,
+    pub verified_time: Option<TimestampDbType>,
+    pub finalized_time: Option<TimestampDbType>,
+    /// Why the block was held back before submission, e.g. a malformed proof.
+    pub rejected_reason: Option<String>,
+    pub rejected_time: Option<TimestampDbType>,
+    pub created_time: TimestampDbType,
+    pub updated_time: TimestampDbType,
+}
+
+/// What a submitter in shadow mode found out about a batch, at most one of the reasons is set.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ShadowOutcome {
+    pub tx_request: Option<serde_json::Value>,
+    pub gas_limit: Option<i64>,
+    pub revert_reason: Option<String>,
+    pub guard_breach: Option<String>,
+    pub rejected_reason: Option<String>,
+}
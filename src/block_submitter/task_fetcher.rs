@@ -1,22 +1,42 @@
+use super::consistency::{BlockData, ConsistencyChecker};
+use super::proof_checker::ProofChecker;
 use super::types::{ContractCall, SubmitBlockArgs};
+use crate::block_submitter::storage::{
+    self,
+    models::{Cursor, ShadowOutcome},
+};
 use crate::block_submitter::Settings;
 use crate::storage::PoolType;
-use crossbeam_channel::Sender;
-use ethers::types::U256;
+use anyhow::anyhow;
 use fluidex_common::db::models;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::Sender;
 
 #[derive(Debug)]
 pub struct TaskFetcher {
     connpool: PoolType,
-    last_block_id: Option<i64>,
+    batch_size: usize,
+    batch_max_wait: Duration,
+    /// When the currently incomplete batch was first seen.
+    batch_since: Option<Instant>,
+    proof_checker: ProofChecker,
+    consistency_checker: Option<ConsistencyChecker>,
+    shadow: bool,
+    /// Block id, public input and proof of the last rejected task, not checked again unless re-proved.
+    rejected: Option<(i64, Vec<u8>, Vec<u8>)>,
 }
 
 impl TaskFetcher {
-    pub fn from_config_with_pool(_config: &Settings, connpool: PoolType) -> Self {
+    pub fn from_config_with_pool(config: &Settings, connpool: PoolType) -> Self {
         Self {
             connpool,
-            last_block_id: None,
+            batch_size: config.batch.size.max(1),
+            batch_max_wait: config.batch.max_wait(),
+            batch_since: None,
+            proof_checker: ProofChecker::from_config(&config.proof),
+            consistency_checker: ConsistencyChecker::from_config(&config.consistency),
+            shadow: config.shadow,
+            rejected: None,
         }
     }
 
@@ -33,23 +53,39 @@ impl TaskFetcher {
     }
 
     async fn run_inner(&mut self, tx: &Sender<ContractCall>) -> Result<(), anyhow::Error> {
+        // wait for room in the queue first, so that nothing is fetched ahead of a slow `EthSender`
+        let permit = tx.reserve().await.map_err(|_| anyhow!("eth sender is gone"))?;
         let mut db_tx = self.connpool.begin().await?;
+        // re-read every time, `EthSender` rewinds the cursor when a transaction gets dropped
+        let last_fetched_block_id = if self.shadow {
+            storage::load_shadow_progress(&mut db_tx).await?
+        } else {
+            storage::load_progress(&mut db_tx).await?.last_fetched_block_id
+        };
 
         #[derive(sqlx::FromRow, Debug, Clone)]
         struct Task {
             block_id: i64,
             public_input: Vec<u8>,
             proof: Vec<u8>,
+            old_root: Option<String>,
+            new_root: String,
+            raw_public_data: Vec<u8>,
         }
 
         let query: &'static str = const_format::formatcp!(
             r#"
-            select t.block_id     as block_id,
-                   t.public_input as public_input,
-                   t.proof        as proof
+            select t.block_id           as block_id,
+                   t.public_input       as public_input,
+                   t.proof              as proof,
+                   prev.new_root        as old_root,
+                   l2b.new_root         as new_root,
+                   l2b.raw_public_data  as raw_public_data
             from {} t
                      inner join {} l2b
                                 on t.block_id = l2b.block_id
+                     left join {} prev
+                                on prev.block_id = t.block_id - 1
             where t.block_id < coalesce((select block_id
                                          from task
                                          where status <> 'proved'
@@ -57,27 +93,112 @@ impl TaskFetcher {
                                          limit 1), 0)
               and t.block_id > $1
               and t.status = 'proved' -- defense filter
-              and l2b.status = 'uncommited'
+              and ($3 or l2b.status = 'uncommited') -- shadow mode follows its own cursor only
             order by t.block_id
-            limit 1"#,
+            limit $2"#,
             models::tablenames::TASK,
             models::tablenames::L2_BLOCK,
+            models::tablenames::L2_BLOCK,
         );
 
-        let task: Option<Task> = sqlx::query_as(query)
-            .bind(self.last_block_id.unwrap_or(-1))
-            .fetch_optional(&mut db_tx)
+        let tasks: Vec<Task> = sqlx::query_as(query)
+            .bind(last_fetched_block_id)
+            .bind(self.batch_size as i64)
+            .bind(self.shadow)
+            .fetch_all(&mut db_tx)
             .await?;
 
-        if let Some(task) = task {
-            let public_inputs: Vec<U256> = serde_json::de::from_slice(&task.public_input)?;
-            let serialized_proof: Vec<U256> = serde_json::de::from_slice(&task.proof)?;
-            tx.try_send(ContractCall::SubmitBlock(SubmitBlockArgs {
-                block_id: task.block_id.into(),
-                public_inputs,
-                serialized_proof,
-            }))?;
-            self.last_block_id = Some(task.block_id);
+        // only consecutive blocks can go into one batch
+        let first_block_id = match tasks.first() {
+            Some(task) => task.block_id,
+            None => {
+                self.batch_since = None;
+                return Ok(());
+            }
+        };
+        let tasks: Vec<Task> = tasks
+            .into_iter()
+            .enumerate()
+            .take_while(|(i, task)| task.block_id == first_block_id + *i as i64)
+            .map(|(_, task)| task)
+            .collect();
+
+        if tasks.len() < self.batch_size {
+            let since = *self.batch_since.get_or_insert_with(Instant::now);
+            if since.elapsed() < self.batch_max_wait {
+                return Ok(());
+            }
+        }
+        self.batch_since = None;
+
+        let mut batch = Vec::with_capacity(tasks.len());
+        for task in &tasks {
+            let task_key = (task.block_id, task.public_input.clone(), task.proof.clone());
+            if self.rejected.as_ref() == Some(&task_key) {
+                break;
+            }
+            let reason = match (
+                serde_json::de::from_slice(&task.public_input),
+                serde_json::de::from_slice(&task.proof),
+            ) {
+                (Ok(public_inputs), Ok(serialized_proof)) => {
+                    let args = SubmitBlockArgs {
+                        block_id: task.block_id.into(),
+                        public_inputs,
+                        serialized_proof,
+                    };
+                    let block = BlockData {
+                        old_root: task.old_root.as_deref(),
+                        new_root: &task.new_root,
+                        raw_public_data: &task.raw_public_data,
+                    };
+                    let inconsistency = match &self.consistency_checker {
+                        Some(checker) => checker.check(&args, &block)?,
+                        None => None,
+                    };
+                    let reason = match inconsistency {
+                        Some(reason) => Some(reason),
+                        None => self.proof_checker.check(&args).await?,
+                    };
+                    match reason {
+                        Some(reason) => reason,
+                        None => {
+                            batch.push(args);
+                            continue;
+                        }
+                    }
+                }
+                (Err(e), _) => format!("malformed public input: {}", e),
+                (_, Err(e)) => format!("malformed proof: {}", e),
+            };
+            // later blocks have to wait, the contract only accepts them in order
+            log::error!("block {} held back: {}", task.block_id, reason);
+            if self.shadow {
+                let outcome = ShadowOutcome {
+                    rejected_reason: Some(reason),
+                    ..Default::default()
+                };
+                storage::record_shadow(&self.connpool, (task.block_id, 1), &outcome).await?;
+            } else {
+                storage::record_rejection(&self.connpool, task.block_id, &reason).await?;
+            }
+            self.rejected = Some(task_key);
+            break;
+        }
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let last_block_id = first_block_id + batch.len() as i64 - 1;
+        let call = if batch.len() == 1 {
+            ContractCall::SubmitBlock(batch.pop().unwrap())
+        } else {
+            ContractCall::SubmitBlocks(batch)
+        };
+        permit.send(call);
+        if self.shadow {
+            storage::set_shadow_progress(&mut db_tx, last_block_id).await?;
+        } else {
+            storage::set_progress(&mut db_tx, Cursor::Fetched, last_block_id).await?;
         }
 
         db_tx.commit().await?;
@@ -1,13 +1,57 @@
 use ethers::types::U256;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum ContractCall {
     SubmitBlock(SubmitBlockArgs),
+    /// Consecutive blocks submitted in a single `submitBlocks` transaction.
+    SubmitBlocks(Vec<SubmitBlockArgs>),
 }
 
-#[derive(Debug, Clone)]
+impl ContractCall {
+    pub fn first_block_id(&self) -> i64 {
+        match self {
+            ContractCall::SubmitBlock(args) => args.block_id.as_u64() as i64,
+            ContractCall::SubmitBlocks(batch) => batch[0].block_id.as_u64() as i64,
+        }
+    }
+
+    /// The first block submitted.
+    pub fn first(&self) -> &SubmitBlockArgs {
+        match self {
+            ContractCall::SubmitBlock(args) => args,
+            ContractCall::SubmitBlocks(batch) => &batch[0],
+        }
+    }
+
+    pub fn block_count(&self) -> usize {
+        match self {
+            ContractCall::SubmitBlock(_) => 1,
+            ContractCall::SubmitBlocks(batch) => batch.len(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct SubmitBlockArgs {
     pub block_id: U256,
     pub public_inputs: Vec<U256>,
     pub serialized_proof: Vec<U256>,
 }
+
+/// Block state kept by the rollup contract, as returned by `getBlockStateByBlockId`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockState {
+    Empty,
+    Committed,
+    Verified,
+}
+
+impl From<u8> for BlockState {
+    fn from(state: u8) -> Self {
+        match state {
+            0 => BlockState::Empty,
+            1 => BlockState::Committed,
+            _ => BlockState::Verified,
+        }
+    }
+}
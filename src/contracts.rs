@@ -1,5 +1,6 @@
 use anyhow::anyhow;
-use ethers::abi::Abi;
+use ethers::abi::{decode, short_signature, Abi, Param, ParamType};
+use std::collections::HashMap;
 use std::fs;
 use std::io;
 use std::str::FromStr;
@@ -18,3 +19,105 @@ pub fn get_abi(path: &str) -> Result<Abi, anyhow::Error> {
         .to_string();
     serde_json::from_str(&abi_string).map_err(|e| anyhow!("{:?}", e))
 }
+
+const ERROR_STRING_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// Decodes revert data using `Error(string)`, `Panic(uint256)` and the custom errors declared in a contract artifact.
+#[derive(Debug, Clone, Default)]
+pub struct RevertDecoder {
+    errors: HashMap<[u8; 4], (String, Vec<ParamType>)>,
+}
+
+pub fn get_revert_decoder(path: &str) -> Result<RevertDecoder, anyhow::Error> {
+    let abi = read_file_to_json_value(path)?;
+    let entries = abi
+        .get("abi")
+        .and_then(|abi| abi.as_array())
+        .ok_or_else(|| anyhow!("couldn't get abi from CONTRACT_FILE"))?;
+
+    let mut errors = HashMap::new();
+    for entry in entries.iter().filter(|e| e.get("type").and_then(|t| t.as_str()) == Some("error")) {
+        let name = entry.get("name").and_then(|n| n.as_str()).unwrap_or_default().to_string();
+        let inputs: Vec<Param> = serde_json::from_value(entry.get("inputs").cloned().unwrap_or_default())?;
+        let types: Vec<ParamType> = inputs.into_iter().map(|p| p.kind).collect();
+        errors.insert(short_signature(&name, &types), (name, types));
+    }
+    Ok(RevertDecoder { errors })
+}
+
+impl RevertDecoder {
+    pub fn decode(&self, data: &[u8]) -> String {
+        if data.len() < 4 {
+            return format!("reverted without reason (0x{})", hex::encode(data));
+        }
+        let (selector, args) = data.split_at(4);
+        let decoded = match selector {
+            s if s == ERROR_STRING_SELECTOR => decode(&[ParamType::String], args).map(|t| t[0].to_string()),
+            s if s == PANIC_SELECTOR => decode(&[ParamType::Uint(256)], args).map(|t| format!("panic {}", t[0])),
+            s => match self.errors.get(s) {
+                Some((name, types)) => decode(types, args).map(|tokens| {
+                    let args: Vec<String> = tokens.iter().map(|t| t.to_string()).collect();
+                    format!("{}({})", name, args.join(", "))
+                }),
+                None => return format!("unknown revert 0x{}", hex::encode(data)),
+            },
+        };
+        decoded.unwrap_or_else(|_| format!("undecodable revert 0x{}", hex::encode(data)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::abi::{encode, Token};
+    use ethers::types::U256;
+
+    fn revert_data(selector: [u8; 4], tokens: &[Token]) -> Vec<u8> {
+        let mut data = selector.to_vec();
+        data.extend(encode(tokens));
+        data
+    }
+
+    fn decoder_with(name: &str, types: Vec<ParamType>) -> RevertDecoder {
+        let mut errors = HashMap::new();
+        errors.insert(short_signature(name, &types), (name.to_string(), types));
+        RevertDecoder { errors }
+    }
+
+    #[test]
+    fn decodes_error_string() {
+        let data = revert_data(ERROR_STRING_SELECTOR, &[Token::String("invalid proof".to_string())]);
+        assert_eq!(RevertDecoder::default().decode(&data), "invalid proof");
+    }
+
+    #[test]
+    fn decodes_panic() {
+        let data = revert_data(PANIC_SELECTOR, &[Token::Uint(U256::from(0x11))]);
+        assert_eq!(RevertDecoder::default().decode(&data), "panic 11");
+    }
+
+    #[test]
+    fn decodes_custom_error() {
+        let decoder = decoder_with("BlockNotCommitted", vec![ParamType::Uint(256)]);
+        let selector = short_signature("BlockNotCommitted", &[ParamType::Uint(256)]);
+        let data = revert_data(selector, &[Token::Uint(U256::from(7))]);
+        assert_eq!(decoder.decode(&data), "BlockNotCommitted(7)");
+    }
+
+    #[test]
+    fn reports_unknown_selector() {
+        assert_eq!(RevertDecoder::default().decode(&[1, 2, 3, 4, 5]), "unknown revert 0x0102030405");
+    }
+
+    #[test]
+    fn reports_missing_reason() {
+        assert_eq!(RevertDecoder::default().decode(&[]), "reverted without reason (0x)");
+    }
+
+    #[test]
+    fn reports_undecodable_args() {
+        let data = [ERROR_STRING_SELECTOR.to_vec(), vec![0xff]].concat();
+        assert_eq!(RevertDecoder::default().decode(&data), "undecodable revert 0x08c379a0ff");
+    }
+}
@@ -10,6 +10,29 @@ pub struct Settings {
     pub db: String,
     pub grpc_upstream: String,
     pub fundings: HashMap<String, DecimalDbType>,
+    /// Users are funded once per asset and campaign, a new campaign funds them again.
+    #[serde(default = "default_campaign")]
+    pub campaign: String,
+    /// Senders funding concurrently, several faucet processes can run side by side as well.
+    #[serde(default = "default_workers")]
+    pub workers: usize,
+    /// Tasks claimed at once by a sender, all of them within one lease.
+    #[serde(default = "default_claim_batch")]
+    pub claim_batch: usize,
+    #[serde(default)]
+    pub lease: LeaseSettings,
+}
+
+fn default_campaign() -> String {
+    "default".to_string()
+}
+
+fn default_workers() -> usize {
+    1
+}
+
+fn default_claim_batch() -> usize {
+    10
 }
 
 impl Settings {
@@ -18,3 +41,63 @@ impl Settings {
         Duration::from_millis(self.send_interval)
     }
 }
+
+/// Retries of a funding whose gRPC call failed or whose sender died while holding it.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct LeaseSettings {
+    /// Seconds after which a claimed task is considered abandoned and claimed again.
+    pub timeout: u64,
+    /// Attempts before a task is marked `failed`.
+    pub max_attempts: i32,
+    /// Seconds to wait after the first failed attempt, doubled after each further one.
+    pub backoff_base: u64,
+    /// Ceiling of the wait between attempts, in seconds.
+    pub backoff_max: u64,
+}
+
+impl Default for LeaseSettings {
+    fn default() -> Self {
+        Self {
+            timeout: 60,
+            max_attempts: 5,
+            backoff_base: 5,
+            backoff_max: 600,
+        }
+    }
+}
+
+impl LeaseSettings {
+    /// Seconds to wait before the next attempt of a task that failed `attempts` times.
+    pub fn backoff(&self, attempts: i32) -> u64 {
+        let exponent = (attempts.max(1) - 1).min(32) as u32;
+        self.backoff_base.saturating_mul(1u64 << exponent).min(self.backoff_max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_the_ceiling() {
+        let lease = LeaseSettings::default();
+        assert_eq!(lease.backoff(0), 5);
+        assert_eq!(lease.backoff(1), 5);
+        assert_eq!(lease.backoff(2), 10);
+        assert_eq!(lease.backoff(3), 20);
+        assert_eq!(lease.backoff(7), 320);
+        assert_eq!(lease.backoff(8), 600);
+        assert_eq!(lease.backoff(i32::MAX), 600);
+    }
+
+    #[test]
+    fn backoff_does_not_overflow() {
+        let lease = LeaseSettings {
+            backoff_base: u64::MAX / 2,
+            backoff_max: u64::MAX,
+            ..Default::default()
+        };
+        assert_eq!(lease.backoff(3), u64::MAX);
+    }
+}
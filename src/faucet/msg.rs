@@ -1,69 +1,68 @@
+use crate::grpc_client::FAUCET_BUSINESS;
 use crate::mq::messages::WrappedMessage;
-use fluidex_common::message::consumer::{Simple, SimpleConsumer, SimpleMessageHandler};
-use fluidex_common::rdkafka;
-use rdkafka::consumer::StreamConsumer;
-use rdkafka::message::{BorrowedMessage, Message};
+use serde::Deserialize;
 
-const UNIFY_TOPIC: &str = "unifyevents";
 const MSG_TYPE_USERS: &str = "registeruser";
+const MSG_TYPE_BALANCES: &str = "balances";
 
-pub fn load_msgs_from_mq(
-    brokers: &str,
-    sender: crossbeam_channel::Sender<WrappedMessage>,
-) -> Option<std::thread::JoinHandle<anyhow::Result<()>>> {
-    let brokers = brokers.to_owned();
-    Some(std::thread::spawn(move || {
-        let rt: tokio::runtime::Runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap();
+pub const MSG_CONSUMER_GROUP: &str = "faucet_msg_consumer";
 
-        let writer = MessageWriter { sender };
-        rt.block_on(async move {
-            let consumer: StreamConsumer = rdkafka::config::ClientConfig::new()
-                .set("bootstrap.servers", brokers)
-                .set("group.id", "faucet_msg_consumer")
-                .set("enable.partition.eof", "false")
-                .set("session.timeout.ms", "6000")
-                .set("enable.auto.commit", "true")
-                .set("auto.offset.reset", "earliest")
-                .create()
-                .unwrap();
-
-            let consumer = std::sync::Arc::new(consumer);
-            loop {
-                let cr_main = SimpleConsumer::new(consumer.as_ref())
-                    .add_topic(UNIFY_TOPIC, Simple::from(&writer))
-                    .unwrap();
-
-                tokio::select! {
-                    _ = tokio::signal::ctrl_c() => {
-                        log::warn!("Ctrl-c received, shutting down");
-                        break;
-                    },
+/// The part of a balance change telling whose it is.
+#[derive(Deserialize)]
+struct BalanceBusiness {
+    business: String,
+}
 
-                    err = cr_main.run_stream(|cr|cr.stream()) => {
-                        log::error!("Kafka consumer error: {}", err);
-                    }
-                }
+pub fn decode(msg_type: &str, msg_payload: &str) -> Result<Option<WrappedMessage>, anyhow::Error> {
+    Ok(Some(match msg_type {
+        MSG_TYPE_USERS => WrappedMessage::User(serde_json::from_str(msg_payload)?),
+        MSG_TYPE_BALANCES => {
+            // every balance change is published, only the faucet's own are of interest
+            let BalanceBusiness { business } = serde_json::from_str(msg_payload)?;
+            if business != FAUCET_BUSINESS {
+                return Ok(None);
             }
-        });
-
-        Ok(())
+            WrappedMessage::Balance(serde_json::from_str(msg_payload)?)
+        }
+        _ => return Ok(None),
     }))
 }
 
-struct MessageWriter {
-    sender: crossbeam_channel::Sender<WrappedMessage>,
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn balance(business: &str) -> String {
+        json!({
+            "timestamp": 1.0,
+            "user_id": 5,
+            "asset": "ETH",
+            "business": business,
+            "change": "1.5",
+            "balance": "1.5",
+            "detail": "{\"id\":1}",
+        })
+        .to_string()
+    }
 
-impl SimpleMessageHandler for &MessageWriter {
-    fn on_message(&self, msg: &BorrowedMessage<'_>) {
-        let msg_type = std::str::from_utf8(msg.key().unwrap()).unwrap();
-        if msg_type != MSG_TYPE_USERS {
-            return;
-        };
+    #[test]
+    fn decodes_faucet_balance_changes_only() {
+        match decode(MSG_TYPE_BALANCES, &balance(FAUCET_BUSINESS)).unwrap() {
+            Some(WrappedMessage::Balance(balance)) => assert_eq!(balance.user_id, 5),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(decode(MSG_TYPE_BALANCES, &balance("trade")).unwrap().is_none());
+        assert!(decode(MSG_TYPE_BALANCES, r#"{"business": "trade", "other": "layout"}"#)
+            .unwrap()
+            .is_none());
+        assert!(decode("orders", "not json").unwrap().is_none());
+    }
 
-        let msg_payload = std::str::from_utf8(msg.payload().unwrap()).unwrap();
-        let data = serde_json::from_str(msg_payload).unwrap();
-        let message = WrappedMessage::User(data);
-        self.sender.try_send(message).unwrap();
+    #[test]
+    fn bad_payloads_are_errors() {
+        assert!(decode(MSG_TYPE_BALANCES, "not json").is_err());
+        assert!(decode(MSG_TYPE_BALANCES, r#"{"business": "regnbue_bridge_faucet"}"#).is_err());
+        assert!(decode(MSG_TYPE_USERS, r#"{"user_id": "five"}"#).is_err());
     }
 }
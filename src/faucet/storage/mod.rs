@@ -1,5 +1,5 @@
 use crate::faucet::Settings;
-use crate::storage::{PoolOptions, PoolType};
+use crate::storage::{run_migrations, PoolOptions, PoolType};
 
 pub mod models;
 
@@ -8,7 +8,7 @@ static MIGRATOR: sqlx::migrate::Migrator = sqlx::migrate!("./migrations/faucet")
 pub async fn from_config(config: &Settings) -> anyhow::Result<PoolType> {
     let db_pool = PoolOptions::new().connect(&config.db).await?;
 
-    MIGRATOR.run(&db_pool).await?;
+    run_migrations(&db_pool, &MIGRATOR, "_faucet_migrations").await?;
 
     Ok(db_pool)
 }
@@ -3,15 +3,18 @@ use serde::Serialize;
 
 pub mod tablenames {
     pub const FAUCET_TX: &str = "faucet_tx";
+    pub const FAUCET_TX_ATTEMPT: &str = "faucet_tx_attempt";
 }
 
-#[derive(sqlx::Type, Debug, Clone, Serialize)]
+#[derive(sqlx::Type, Debug, Clone, PartialEq, Serialize)]
 #[sqlx(type_name = "tx_status", rename_all = "snake_case")]
 pub enum TxStatus {
     Proposed,
     Claimed,
     Sent,
     Confirmed,
+    /// Gave up after the configured attempts.
+    Failed,
 }
 
 #[derive(sqlx::FromRow, Debug, Clone, Serialize)]
@@ -20,7 +23,39 @@ pub struct FaucetTx {
     pub to_user: i32,
     pub asset: String,
     pub amount: DecimalDbType,
+    /// Unset for fundings proposed before campaigns existed.
+    pub campaign: Option<String>,
     pub status: TxStatus,
+    pub claimed_at: Option<TimestampDbType>,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub next_attempt_at: TimestampDbType,
     pub created_time: TimestampDbType,
     pub updated_time: TimestampDbType,
 }
+
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Serialize)]
+#[sqlx(type_name = "attempt_outcome", rename_all = "snake_case")]
+pub enum AttemptOutcome {
+    Succeeded,
+    /// Refused by the matchengine as already applied.
+    Duplicate,
+    Failed,
+}
+
+#[derive(sqlx::FromRow, Debug, Clone, Serialize)]
+pub struct FaucetTxAttempt {
+    pub id: i32,
+    pub faucet_tx_id: i32,
+    pub attempt: i32,
+    /// Unset while the call is in flight, or if the sender died during it.
+    pub outcome: Option<AttemptOutcome>,
+    /// gRPC status code, unset if the matchengine could not be reached.
+    pub grpc_code: Option<i32>,
+    pub error_code: Option<String>,
+    pub error_message: Option<String>,
+    pub business: Option<String>,
+    pub business_id: Option<i64>,
+    pub started_time: TimestampDbType,
+    pub finished_time: Option<TimestampDbType>,
+}
@@ -1,8 +1,11 @@
-use super::msg::load_msgs_from_mq;
+use super::msg;
 use crate::faucet::storage::models;
 use crate::faucet::Settings;
-use crate::mq::messages::WrappedMessage;
+use crate::grpc_client::FAUCET_BUSINESS;
+use crate::mq::messages::{BalanceMessage, WrappedMessage};
+use crate::mq::{load_msgs_from_mq, PositionedMessage, MSG_CHANNEL_SIZE};
 use crate::storage::{DecimalDbType, PoolType};
+use anyhow::anyhow;
 use std::collections::HashMap;
 
 #[derive(Debug)]
@@ -10,6 +13,7 @@ pub struct TxProposer {
     brokers: String,
     connpool: PoolType,
     fundings: HashMap<String, DecimalDbType>,
+    campaign: String,
 }
 
 impl TxProposer {
@@ -18,47 +22,123 @@ impl TxProposer {
             brokers: config.brokers.to_owned(),
             connpool,
             fundings: config.fundings.clone(),
+            campaign: config.campaign.clone(),
         }
     }
 
     pub async fn run(&self) {
-        let (msg_sender, msg_receiver) = crossbeam_channel::unbounded();
-        let loader_thread = load_msgs_from_mq(&self.brokers, msg_sender);
+        let (msg_sender, mut msg_receiver) = tokio::sync::mpsc::channel(MSG_CHANNEL_SIZE);
+        let loader_thread = load_msgs_from_mq(&self.brokers, msg::MSG_CONSUMER_GROUP, msg::decode, msg_sender);
 
-        for msg in msg_receiver.iter() {
-            match msg {
+        while let Some(PositionedMessage { message, .. }) = msg_receiver.recv().await {
+            match message {
                 WrappedMessage::User(user) => {
                     self.propose_fundings(user.user_id).await.unwrap();
                 }
+                WrappedMessage::Balance(balance) if balance.business == FAUCET_BUSINESS => {
+                    if let Err(e) = self.confirm_funding(&balance).await {
+                        log::error!("confirm funding {:?}, error: {:?}", balance.detail, e);
+                    }
+                }
+                WrappedMessage::Transfer(_) | WrappedMessage::Balance(_) => {}
             }
         }
 
         loader_thread.map(|h| h.join().expect("loader thread failed"));
     }
 
+    /// Proposes the configured fundings once per user, asset and campaign. A redelivered
+    /// registration finds them already proposed and skips them. Fundings proposed before
+    /// campaigns existed have no campaign and count as the `default` one.
     async fn propose_fundings(&self, user_id: i32) -> Result<(), anyhow::Error> {
+        let mut skipped = 0;
         for (asset, amount) in &self.fundings {
             let stmt = format!(
-                "insert into {} (to_user, asset, amount) values ($1, $2, $3)",
-                models::tablenames::FAUCET_TX
+                "insert into {table} (to_user, asset, amount, campaign)
+                select $1, $2, $3, $4
+                where $4 <> 'default' or not exists (
+                    select 1 from {table} where to_user = $1 and asset = $2 and campaign is null)
+                on conflict (to_user, asset, campaign) do nothing",
+                table = models::tablenames::FAUCET_TX
             );
-            if let Err(e) = sqlx::query(&stmt)
+            match sqlx::query(&stmt)
                 .bind(user_id)
                 .bind(asset)
                 .bind(amount) // TODO: to_string?
+                .bind(&self.campaign)
                 .execute(&self.connpool)
                 .await
             {
-                log::error!(
+                Ok(res) if res.rows_affected() == 0 => skipped += 1,
+                Ok(_) => {}
+                Err(e) => log::error!(
                     "propose funding for user {:?}, asset: {:?}, amount: {:?} , error: {:?}",
                     user_id,
                     asset,
                     amount,
                     e
-                )
+                ),
             }
         }
+        if skipped > 0 {
+            log::info!(
+                "skipped {} of {} fundings already proposed for user {:?} in campaign {:?}",
+                skipped,
+                self.fundings.len(),
+                user_id,
+                self.campaign
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Marks the funding a faucet balance update was made for as `confirmed`, once the update
+    /// matches its row. A mismatch is recorded in `last_error` and the row left as it is.
+    async fn confirm_funding(&self, balance: &BalanceMessage) -> Result<(), anyhow::Error> {
+        let detail: serde_json::Value = serde_json::from_str(&balance.detail)?;
+        let id = detail["id"]
+            .as_i64()
+            .ok_or_else(|| anyhow!("no faucet_tx id in detail {:?}", balance.detail))?;
+
+        let query = format!(
+            "select id, to_user, asset, amount, campaign, status, claimed_at, attempts, last_error, next_attempt_at, created_time, updated_time
+            from {} where id = $1",
+            models::tablenames::FAUCET_TX
+        );
+        let task: models::FaucetTx = sqlx::query_as(&query)
+            .bind(id)
+            .fetch_optional(&self.connpool)
+            .await?
+            .ok_or_else(|| anyhow!("faucet_tx {} not found", id))?;
+        if task.status == models::TxStatus::Confirmed {
+            return Ok(());
+        }
+
+        let mismatch = if task.to_user as u32 != balance.user_id {
+            Some(format!("funded user {}, expected {}", balance.user_id, task.to_user))
+        } else if task.asset != balance.asset {
+            Some(format!("funded asset {}, expected {}", balance.asset, task.asset))
+        } else if task.amount != balance.change {
+            Some(format!("funded amount {}, expected {}", balance.change, task.amount))
+        } else {
+            None
+        };
+        if let Some(mismatch) = mismatch {
+            log::error!("faucet_tx {} mismatch: {}", id, mismatch);
+            let stmt = format!("update {} set last_error = $1 where id = $2", models::tablenames::FAUCET_TX);
+            sqlx::query(&stmt).bind(mismatch).bind(id).execute(&self.connpool).await?;
+            return Ok(());
+        }
 
+        // the update can land before the sender records it as sent
+        let stmt = format!("update {} set status = $1 where id = $2", models::tablenames::FAUCET_TX);
+        sqlx::query(&stmt)
+            .bind(models::TxStatus::Confirmed)
+            .bind(id)
+            .execute(&self.connpool)
+            .await?;
+        log::info!("faucet_tx {} confirmed", id);
         Ok(())
     }
 }
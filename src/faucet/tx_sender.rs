@@ -1,5 +1,6 @@
+use crate::faucet::config::LeaseSettings;
 use crate::faucet::{storage::models, Settings};
-use crate::grpc_client::GrpcClient;
+use crate::grpc_client::{self, GrpcClient, FAUCET_BUSINESS};
 use crate::storage::PoolType;
 use anyhow::anyhow;
 use std::time::Duration;
@@ -9,6 +10,9 @@ pub struct TxSender {
     connpool: PoolType,
     send_interval: Duration,
     grpc_client: GrpcClient,
+    lease: LeaseSettings,
+    workers: usize,
+    claim_batch: usize,
 }
 
 impl TxSender {
@@ -19,34 +23,65 @@ impl TxSender {
             grpc_client: GrpcClient {
                 upstream: config.grpc_upstream.clone(),
             },
+            lease: config.lease.clone(),
+            workers: config.workers.max(1),
+            claim_batch: config.claim_batch.max(1),
         }
     }
 
     pub async fn run(&self) {
-        let mut timer = tokio::time::interval(self.send_interval);
+        futures::future::join_all((0..self.workers).map(|worker| self.run_worker(worker))).await;
+    }
 
-        // TODO: use worker_pool for multiple workers
+    async fn run_worker(&self, worker: usize) {
+        let mut timer = tokio::time::interval(self.send_interval);
         loop {
             timer.tick().await;
-            log::debug!("ticktock!");
+            log::debug!("ticktock! worker {}", worker);
+
+            // keep going without waiting for the next tick while there is a backlog
+            loop {
+                match self.run_inner().await {
+                    Ok(claimed) if claimed == self.claim_batch => continue,
+                    Ok(_) => break,
+                    Err(e) => {
+                        log::error!("worker {}: {}", worker, e);
+                        break;
+                    }
+                }
+            }
+        }
+    }
 
-            if let Err(e) = self.run_inner().await {
+    /// Funds a batch of tasks, returns how many were claimed.
+    async fn run_inner(&self) -> Result<usize, anyhow::Error> {
+        let tasks = self.claim_tasks().await.map_err(|e| anyhow!("claim_tasks: {:?}", e))?;
+        for task in &tasks {
+            if let Err(e) = self.send_one(task).await {
                 log::error!("{}", e);
-            };
+            }
         }
+        Ok(tasks.len())
     }
 
-    async fn run_inner(&self) -> Result<(), anyhow::Error> {
-        let task = self.claim_one_task().await.map_err(|e| anyhow!("claim_one_task: {:?}", e))?;
-        if task.is_none() {
-            return Ok(());
+    async fn send_one(&self, task: &models::FaucetTx) -> Result<(), anyhow::Error> {
+        let attempt_id = self.start_attempt(task).await?;
+        let res = self.grpc_client.fund(task).await;
+        self.finish_attempt(attempt_id, task, res.as_ref().err()).await?;
+
+        if let Err(e) = res {
+            if !grpc_client::is_duplicate(&e) {
+                self.mark_attempt_failed(task, &e.to_string()).await?;
+                return Err(anyhow!(
+                    "grpc_client send faucet tx {} (attempt {}): {:?}",
+                    task.id,
+                    task.attempts,
+                    e
+                ));
+            }
+            // an earlier attempt went through, its answer got lost
+            log::warn!("faucet_tx {} already funded: {}", task.id, e);
         }
-        let task = task.unwrap();
-
-        self.grpc_client
-            .fund(&task)
-            .await
-            .map_err(|e| anyhow!("grpc_client send faucet tx: {:?}", e))?;
 
         // if task.to_user > 1 {
         //     self.grpc_client
@@ -55,9 +90,7 @@ impl TxSender {
         //         .map_err(|e| anyhow!("grpc_client send transfer tx: {:?}", e))?;
         // }
 
-        self.mark_fund_sent(task.clone().id)
-            .await
-            .map_err(|_| anyhow!("mark_fund_sent in db"))?;
+        self.mark_fund_sent(task.id).await.map_err(|_| anyhow!("mark_fund_sent in db"))?;
 
         log::info!(
             "({:?}) amout of asset ({:?}) funded to {:?} successfully!",
@@ -69,39 +102,138 @@ impl TxSender {
         Ok(())
     }
 
-    async fn claim_one_task(&self) -> Result<Option<models::FaucetTx>, anyhow::Error> {
-        let mut tx = self.connpool.begin().await?;
-
-        let query = format!(
-            "select id, to_user, asset, amount, status, created_time, updated_time
-            from {}
-            where status = $1 limit 1",
+    /// Claims up to `claim_batch` proposed tasks that are due, or whose lease expired, for `lease.timeout`.
+    /// Rows locked by another worker or process are skipped rather than waited for.
+    async fn claim_tasks(&self) -> Result<Vec<models::FaucetTx>, anyhow::Error> {
+        // abandoned on their last attempt, nothing left to retry
+        let stmt = format!(
+            "update {} set status = $1, last_error = coalesce(last_error, 'lease expired')
+            where status = $2 and claimed_at < CURRENT_TIMESTAMP - $3 * interval '1 second' and attempts >= $4",
             models::tablenames::FAUCET_TX
         );
+        sqlx::query(&stmt)
+            .bind(models::TxStatus::Failed)
+            .bind(models::TxStatus::Claimed)
+            .bind(self.lease.timeout as f64)
+            .bind(self.lease.max_attempts)
+            .execute(&self.connpool)
+            .await?;
 
-        let fetch_res = sqlx::query_as::<_, models::FaucetTx>(&query)
+        let stmt = format!(
+            "with due as (
+                select id from {table}
+                where (status = $1 and next_attempt_at <= CURRENT_TIMESTAMP)
+                   or (status = $2 and claimed_at < CURRENT_TIMESTAMP - $3 * interval '1 second')
+                order by id limit $4
+                for update skip locked
+            )
+            update {table} t set status = $2, claimed_at = CURRENT_TIMESTAMP, attempts = t.attempts + 1
+            from due where t.id = due.id
+            returning t.id, t.to_user, t.asset, t.amount, t.campaign, t.status, t.claimed_at, t.attempts, t.last_error,
+                t.next_attempt_at, t.created_time, t.updated_time",
+            table = models::tablenames::FAUCET_TX
+        );
+        let mut tasks: Vec<models::FaucetTx> = sqlx::query_as(&stmt)
             .bind(models::TxStatus::Proposed)
-            .fetch_optional(&mut tx)
+            .bind(models::TxStatus::Claimed)
+            .bind(self.lease.timeout as f64)
+            .bind(self.claim_batch as i64)
+            .fetch_all(&self.connpool)
+            .await?;
+        tasks.sort_by_key(|t| t.id);
+        Ok(tasks)
+    }
+
+    async fn start_attempt(&self, task: &models::FaucetTx) -> Result<i32, anyhow::Error> {
+        let stmt = format!(
+            "insert into {} (faucet_tx_id, attempt) values ($1, $2) returning id",
+            models::tablenames::FAUCET_TX_ATTEMPT
+        );
+        let id = sqlx::query_scalar(&stmt)
+            .bind(task.id)
+            .bind(task.attempts)
+            .fetch_one(&self.connpool)
             .await?;
+        Ok(id)
+    }
 
-        if let Some(ref t) = fetch_res {
-            let stmt = format!("update {} set status = $1 where id = $2", models::tablenames::FAUCET_TX);
+    /// Records how the call went and, when it failed, the gRPC status code and message.
+    async fn finish_attempt(&self, id: i32, task: &models::FaucetTx, error: Option<&anyhow::Error>) -> Result<(), anyhow::Error> {
+        let outcome = match error {
+            None => models::AttemptOutcome::Succeeded,
+            Some(e) if grpc_client::is_duplicate(e) => models::AttemptOutcome::Duplicate,
+            Some(_) => models::AttemptOutcome::Failed,
+        };
+        let status = error.and_then(|e| e.downcast_ref::<tonic::Status>());
+        let error_message = match (status, error) {
+            (Some(status), _) => Some(status.message().to_string()),
+            (None, Some(e)) => Some(e.to_string()),
+            (None, None) => None,
+        };
+        let stmt = format!(
+            "update {} set outcome = $1, grpc_code = $2, error_code = $3, error_message = $4, business = $5, business_id = $6,
+                finished_time = CURRENT_TIMESTAMP
+            where id = $7",
+            models::tablenames::FAUCET_TX_ATTEMPT
+        );
+        sqlx::query(&stmt)
+            .bind(outcome)
+            .bind(status.map(|status| status.code() as i32))
+            .bind(status.map(|status| format!("{:?}", status.code())))
+            .bind(error_message)
+            .bind(FAUCET_BUSINESS)
+            .bind(task.id as i64)
+            .bind(id)
+            .execute(&self.connpool)
+            .await?;
+        Ok(())
+    }
+
+    /// Releases the claim on `task` for a retry after the backoff, or fails it for good on its last attempt.
+    async fn mark_attempt_failed(&self, task: &models::FaucetTx, error: &str) -> Result<(), anyhow::Error> {
+        if task.attempts >= self.lease.max_attempts {
+            let stmt = format!(
+                "update {} set status = $1, last_error = $2 where id = $3 and status = $4",
+                models::tablenames::FAUCET_TX
+            );
             sqlx::query(&stmt)
+                .bind(models::TxStatus::Failed)
+                .bind(error)
+                .bind(task.id)
                 .bind(models::TxStatus::Claimed)
-                .bind(t.clone().id)
-                .execute(&mut tx)
+                .execute(&self.connpool)
                 .await?;
-        };
+            log::error!("faucet_tx {} failed after {} attempts: {}", task.id, task.attempts, error);
+            return Ok(());
+        }
 
-        tx.commit().await?;
-        Ok(fetch_res)
+        let stmt = format!(
+            "update {} set status = $1, claimed_at = NULL, last_error = $2,
+                next_attempt_at = CURRENT_TIMESTAMP + $3 * interval '1 second'
+            where id = $4 and status = $5",
+            models::tablenames::FAUCET_TX
+        );
+        sqlx::query(&stmt)
+            .bind(models::TxStatus::Proposed)
+            .bind(error)
+            .bind(self.lease.backoff(task.attempts) as f64)
+            .bind(task.id)
+            .bind(models::TxStatus::Claimed)
+            .execute(&self.connpool)
+            .await?;
+        Ok(())
     }
 
+    /// Leaves the task alone if its balance update was confirmed in the meantime.
     async fn mark_fund_sent(&self, id: i32) -> Result<(), anyhow::Error> {
-        let stmt = format!("update {} set status = $1 where id = $2", models::tablenames::FAUCET_TX);
+        let stmt = format!(
+            "update {} set status = $1 where id = $2 and status = $3",
+            models::tablenames::FAUCET_TX
+        );
         sqlx::query(&stmt)
             .bind(models::TxStatus::Sent)
             .bind(id)
+            .bind(models::TxStatus::Claimed)
             .execute(&self.connpool)
             .await?;
         Ok(())
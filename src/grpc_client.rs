@@ -1,7 +1,24 @@
 use crate::faucet::storage::models;
+use crate::tele_in::storage::models::DepositTx;
 use anyhow::anyhow;
 use orchestra::rpc::exchange::*;
 
+/// `business` of the balance updates crediting L1 deposits.
+pub const TELE_IN_BUSINESS: &str = "regnbue_bridge_tele_in";
+/// What the matchengine answers to a balance update repeating a `(business, business_id)` it has applied.
+const DUPLICATE_REQUEST_CODE: tonic::Code = tonic::Code::InvalidArgument;
+const DUPLICATE_REQUEST_MESSAGE: &str = "duplicate request";
+
+/// Whether `e` is the matchengine refusing a balance update it has already applied.
+pub fn is_duplicate(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<tonic::Status>().map_or(false, |status| {
+        status.code() == DUPLICATE_REQUEST_CODE && status.message() == DUPLICATE_REQUEST_MESSAGE
+    })
+}
+
+/// `business` of the balance updates funding faucet users.
+pub const FAUCET_BUSINESS: &str = "regnbue_bridge_faucet";
+
 #[derive(Debug, Clone)]
 pub struct GrpcClient {
     pub upstream: String,
@@ -14,10 +31,10 @@ impl GrpcClient {
         let request = tonic::Request::new(BalanceUpdateRequest {
             user_id: tx.to_user as u32,
             asset: tx.asset.clone(),
-            business: "regnbue_bridge_faucet".to_string(),
+            business: FAUCET_BUSINESS.to_string(),
             business_id: tx.id as u64,
             delta: tx.amount.to_string(),
-            detail: serde_json::json!({"id": "", "faucet_tx time": tx.created_time}).to_string(),
+            detail: serde_json::json!({"id": tx.id, "faucet_tx time": tx.created_time}).to_string(),
             log_metadata: None,
             signature: None,
         });
@@ -28,6 +45,32 @@ impl GrpcClient {
         }
     }
 
+    pub async fn deposit(&self, user_id: u32, deposit: &DepositTx) -> Result<BalanceUpdateResponse, anyhow::Error> {
+        let mut client = matchengine_client::MatchengineClient::connect(self.upstream.clone()).await?;
+
+        let request = tonic::Request::new(BalanceUpdateRequest {
+            user_id,
+            asset: deposit
+                .asset
+                .clone()
+                .ok_or_else(|| anyhow!("deposit {} without asset", deposit.id))?,
+            business: TELE_IN_BUSINESS.to_string(),
+            business_id: deposit.business_id as u64,
+            delta: deposit
+                .amount
+                .ok_or_else(|| anyhow!("deposit {} without amount", deposit.id))?
+                .to_string(),
+            detail: serde_json::json!({"l1_tx_hash": deposit.l1_tx_hash, "l1_log_index": deposit.l1_log_index}).to_string(),
+            log_metadata: None,
+            signature: None,
+        });
+        log::debug!("grpc_client sending deposit (id: {:?})", deposit.id);
+        match client.balance_update(request).await {
+            Ok(resp) => Ok(resp.into_inner()),
+            Err(e) => Err(anyhow!(e)),
+        }
+    }
+
     // pub async fn mock_transfer(&self, tx: &models::FaucetTx) -> Result<TransferResponse, anyhow::Error> {
     //     assert!(tx.to_user > 1);
 
@@ -48,3 +91,20 @@ impl GrpcClient {
     //     }
     // }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_duplicate_request_reply_is_a_duplicate() {
+        assert!(is_duplicate(&anyhow::Error::new(tonic::Status::invalid_argument(
+            "duplicate request"
+        ))));
+        assert!(!is_duplicate(&anyhow::Error::new(tonic::Status::internal("duplicate request"))));
+        assert!(!is_duplicate(&anyhow::Error::new(tonic::Status::invalid_argument(
+            "invalid asset, duplicate decimals"
+        ))));
+        assert!(!is_duplicate(&anyhow!("duplicate request")));
+    }
+}
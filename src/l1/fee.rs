@@ -0,0 +1,103 @@
+use anyhow::anyhow;
+use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FeeMode {
+    Legacy,
+    Eip1559,
+    /// EIP-1559 when the latest block has a `baseFeePerGas`, legacy otherwise (e.g. ganache).
+    Auto,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct FeeSettings {
+    pub mode: FeeMode,
+    /// Percentage of the node's suggested `gasPrice` paid by legacy transactions.
+    pub gas_price_percent: u64,
+    /// `maxPriorityFeePerGas` of EIP-1559 transactions, in gwei.
+    pub priority_fee: u64,
+    /// `maxFeePerGas` is the latest base fee times this, plus the priority fee.
+    pub base_fee_multiplier: u64,
+}
+
+impl Default for FeeSettings {
+    fn default() -> Self {
+        Self {
+            mode: FeeMode::Auto,
+            gas_price_percent: 100,
+            priority_fee: 2,
+            base_fee_multiplier: 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FeeEstimator {
+    settings: FeeSettings,
+}
+
+impl FeeEstimator {
+    pub fn from_config(config: &FeeSettings) -> Self {
+        Self { settings: config.clone() }
+    }
+
+    /// Converts `tx` into the type of the configured mode and fills in its fees.
+    pub async fn apply<M: Middleware>(&self, client: &M, tx: TypedTransaction) -> Result<TypedTransaction, anyhow::Error> {
+        let latest = client
+            .get_block(BlockNumber::Latest)
+            .await
+            .map_err(|e| anyhow!("get latest block: {:?}", e))?
+            .ok_or_else(|| anyhow!("latest block not found"))?;
+
+        let base_fee = match (self.settings.mode, latest.base_fee_per_gas) {
+            (FeeMode::Legacy, _) | (FeeMode::Auto, None) => None,
+            (_, Some(base_fee)) => Some(base_fee),
+            (FeeMode::Eip1559, None) => return Err(anyhow!("fee mode is eip1559 but the chain has no base fee")),
+        };
+
+        match base_fee {
+            None => {
+                let gas_price = client.get_gas_price().await.map_err(|e| anyhow!("get gas price: {:?}", e))?;
+                let mut request = into_legacy(tx);
+                request.gas_price = Some(gas_price * self.settings.gas_price_percent / 100);
+                Ok(TypedTransaction::Legacy(request))
+            }
+            Some(base_fee) => {
+                let priority_fee = U256::from(self.settings.priority_fee) * U256::exp10(9);
+                let mut request = into_eip1559(tx);
+                request.max_priority_fee_per_gas = Some(priority_fee);
+                request.max_fee_per_gas = Some(base_fee * self.settings.base_fee_multiplier + priority_fee);
+                Ok(TypedTransaction::Eip1559(request))
+            }
+        }
+    }
+}
+
+fn into_legacy(tx: TypedTransaction) -> TransactionRequest {
+    match tx {
+        TypedTransaction::Legacy(inner) => inner,
+        TypedTransaction::Eip2930(inner) => inner.tx,
+        TypedTransaction::Eip1559(inner) => inner.into(),
+    }
+}
+
+fn into_eip1559(tx: TypedTransaction) -> Eip1559TransactionRequest {
+    let inner = match tx {
+        TypedTransaction::Eip1559(inner) => return inner,
+        TypedTransaction::Legacy(inner) => inner,
+        TypedTransaction::Eip2930(inner) => inner.tx,
+    };
+    Eip1559TransactionRequest {
+        from: inner.from,
+        to: inner.to,
+        gas: inner.gas,
+        value: inner.value,
+        data: inner.data,
+        nonce: inner.nonce,
+        ..Default::default()
+    }
+}
@@ -0,0 +1,67 @@
+//! Follows the L1 head, pushed by a `newHeads` subscription when the endpoint is a WebSocket
+//! or IPC one, and polled otherwise.
+
+use super::transport::{self, L1Provider};
+use ethers::prelude::*;
+use futures::StreamExt;
+use std::time::Duration;
+use tokio::sync::watch;
+
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Spawns the follower and returns the latest L1 block number it has seen, starting at 0. Heads
+/// are polled through `client`, which fails over between endpoints, while `url` cannot be subscribed to.
+pub fn watch(url: String, client: L1Provider, poll_interval: Duration) -> watch::Receiver<u64> {
+    let (tx, rx) = watch::channel(0);
+    tokio::spawn(async move {
+        loop {
+            let res = if transport::is_pubsub(&url) {
+                subscribe(&url, &tx).await
+            } else {
+                poll(&client, poll_interval, &tx).await
+            };
+            if tx.is_closed() {
+                return;
+            }
+            if let Err(e) = res {
+                log::error!("l1 heads from {}: {}", url, e);
+            }
+            // keeps the heads moving until the next try
+            let _ = tokio::time::timeout(RECONNECT_INTERVAL, poll(&client, poll_interval, &tx)).await;
+        }
+    });
+    rx
+}
+
+async fn subscribe(url: &str, tx: &watch::Sender<u64>) -> Result<(), anyhow::Error> {
+    if transport::is_ws(url) {
+        follow(Provider::new(Ws::connect(url).await?), tx).await
+    } else {
+        follow(Provider::new(Ipc::connect(url).await?), tx).await
+    }
+}
+
+async fn follow<P: PubsubClient>(provider: Provider<P>, tx: &watch::Sender<u64>) -> Result<(), anyhow::Error> {
+    let mut heads = provider.subscribe_blocks().await?;
+    while let Some(head) = heads.next().await {
+        if let Some(number) = head.number {
+            log::debug!("new head {}", number);
+            if tx.send(number.as_u64()).is_err() {
+                return Ok(());
+            }
+        }
+    }
+    Err(anyhow::anyhow!("new heads subscription closed"))
+}
+
+async fn poll(client: &L1Provider, poll_interval: Duration, tx: &watch::Sender<u64>) -> Result<(), anyhow::Error> {
+    let mut timer = tokio::time::interval(poll_interval);
+    loop {
+        timer.tick().await;
+
+        let number = client.get_block_number().await?.as_u64();
+        if number != *tx.borrow() && tx.send(number).is_err() {
+            return Ok(());
+        }
+    }
+}
@@ -0,0 +1,11 @@
+//! Building blocks shared by the services that talk to L1.
+
+pub mod fee;
+pub mod heads;
+pub mod signer;
+pub mod transport;
+pub mod units;
+
+pub use fee::{FeeEstimator, FeeMode, FeeSettings};
+pub use signer::{L1Signer, SignerSettings};
+pub use transport::{L1Provider, RpcSettings};
@@ -0,0 +1,265 @@
+//! Signers of L1 transactions, so that operator secrets can stay out of the config files.
+
+use anyhow::anyhow;
+use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use serde::Deserialize;
+use serde_json::Value;
+use std::convert::TryFrom;
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SignerSettings {
+    /// Hex-encoded private key, read from the `env` variable or from `file`.
+    PrivateKey { env: Option<String>, file: Option<String> },
+    /// Encrypted JSON keystore, its password read from `password_env` or `password_file`.
+    /// A plaintext `password` is still accepted for older configs.
+    Keystore {
+        path: String,
+        password_env: Option<String>,
+        password_file: Option<String>,
+        password: Option<String>,
+    },
+    /// External signer answering `eth_signTransaction`, such as clef or web3signer. A dev node
+    /// with an unlocked account stands in for it locally. Signs for its first `eth_accounts`
+    /// entry unless `address` is given.
+    Remote { url: String, address: Option<String> },
+}
+
+impl SignerSettings {
+    /// The `signer` section if any, otherwise the top-level `keystore` and `password` of older configs.
+    pub fn or_keystore(signer: &Option<Self>, keystore: &Option<String>, password: &Option<String>) -> Result<Self, anyhow::Error> {
+        match (signer, keystore) {
+            (Some(signer), _) => Ok(signer.clone()),
+            (None, Some(keystore)) => Ok(SignerSettings::Keystore {
+                path: keystore.clone(),
+                password_env: None,
+                password_file: None,
+                password: password.clone(),
+            }),
+            (None, None) => Err(anyhow!("either signer or keystore has to be configured")),
+        }
+    }
+}
+
+/// Reads a secret from the `env` variable or else from `file`.
+fn read_secret(what: &str, env: &Option<String>, file: &Option<String>) -> Result<String, anyhow::Error> {
+    if let Some(env) = env {
+        return std::env::var(env).map_err(|e| anyhow!("{} from env {}: {}", what, env, e));
+    }
+    if let Some(file) = file {
+        let secret = std::fs::read_to_string(file).map_err(|e| anyhow!("{} from file {}: {}", what, file, e))?;
+        return Ok(secret.trim().to_string());
+    }
+    Err(anyhow!("no source configured for the {}", what))
+}
+
+#[derive(Debug)]
+pub enum L1Signer {
+    Local(LocalWallet),
+    Remote { client: Provider<Http>, address: Address },
+}
+
+impl L1Signer {
+    pub async fn from_config(config: &SignerSettings, chain_id: u64) -> Result<Self, anyhow::Error> {
+        let signer = match config {
+            SignerSettings::PrivateKey { env, file } => {
+                let key = read_secret("private key", env, file)?;
+                let wallet = key.trim_start_matches("0x").parse::<LocalWallet>()?;
+                L1Signer::Local(wallet.with_chain_id(chain_id))
+            }
+            SignerSettings::Keystore {
+                path,
+                password_env,
+                password_file,
+                password,
+            } => {
+                let password = match password {
+                    Some(password) if password_env.is_none() && password_file.is_none() => password.clone(),
+                    _ => read_secret("keystore password", password_env, password_file)?,
+                };
+                L1Signer::Local(LocalWallet::decrypt_keystore(path, password)?.with_chain_id(chain_id))
+            }
+            SignerSettings::Remote { url, address } => {
+                let client = Provider::<Http>::try_from(url.as_str())?;
+                let address = match address {
+                    Some(address) => address.parse::<Address>()?,
+                    None => *client
+                        .get_accounts()
+                        .await?
+                        .first()
+                        .ok_or_else(|| anyhow!("remote signer {} has no account", url))?,
+                };
+                L1Signer::Remote { client, address }
+            }
+        };
+        log::info!("signing L1 transactions as {:#x}", signer.address());
+        Ok(signer)
+    }
+
+    pub fn address(&self) -> Address {
+        match self {
+            L1Signer::Local(wallet) => wallet.address(),
+            L1Signer::Remote { address, .. } => *address,
+        }
+    }
+
+    /// Signs a filled `tx` and returns it RLP-encoded, ready for `eth_sendRawTransaction`.
+    pub async fn sign(&self, tx: &TypedTransaction) -> Result<Bytes, anyhow::Error> {
+        match self {
+            L1Signer::Local(wallet) => {
+                let signature = wallet.sign_transaction(tx).await?;
+                Ok(tx.rlp_signed(&signature))
+            }
+            L1Signer::Remote { client, address } => {
+                let mut tx = tx.clone();
+                tx.set_from(*address);
+                let signed: Value = client.request("eth_signTransaction", [tx]).await?;
+                // clef answers `{raw, tx}`, web3signer the bare raw transaction
+                let raw = match &signed {
+                    Value::Object(map) => map.get("raw"),
+                    other => Some(other),
+                }
+                .and_then(Value::as_str)
+                .ok_or_else(|| anyhow!("unexpected eth_signTransaction answer: {}", signed))?;
+                Ok(hex::decode(raw.trim_start_matches("0x"))?.into())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    const ACCOUNT: &str = "0x00000000000000000000000000000000000000aa";
+
+    /// A JSON-RPC endpoint answering `eth_accounts` with `ACCOUNT` and `eth_signTransaction`
+    /// with `signed`, recording every request it gets.
+    async fn mock_signer(signed: Value) -> (String, Arc<Mutex<Vec<Value>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, signed.clone(), seen.clone()));
+            }
+        });
+        (url, requests)
+    }
+
+    async fn serve(mut stream: TcpStream, signed: Value, seen: Arc<Mutex<Vec<Value>>>) {
+        let mut buf = Vec::new();
+        loop {
+            let header_end = loop {
+                if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break pos + 4;
+                }
+                if !read_more(&mut stream, &mut buf).await {
+                    return;
+                }
+            };
+            let headers = String::from_utf8_lossy(&buf[..header_end]).to_ascii_lowercase();
+            let length = headers
+                .lines()
+                .find_map(|line| line.strip_prefix("content-length:"))
+                .map(|value| value.trim().parse::<usize>().unwrap())
+                .unwrap_or(0);
+            while buf.len() < header_end + length {
+                if !read_more(&mut stream, &mut buf).await {
+                    return;
+                }
+            }
+            let request: Value = serde_json::from_slice(&buf[header_end..header_end + length]).unwrap();
+            buf.drain(..header_end + length);
+
+            let result = match request["method"].as_str() {
+                Some("eth_accounts") => json!([ACCOUNT]),
+                Some("eth_signTransaction") => signed.clone(),
+                method => panic!("unexpected method {:?}", method),
+            };
+            let body = json!({"jsonrpc": "2.0", "id": request["id"], "result": result}).to_string();
+            seen.lock().unwrap().push(request);
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+        }
+    }
+
+    async fn read_more(stream: &mut TcpStream, buf: &mut Vec<u8>) -> bool {
+        let mut chunk = [0u8; 4096];
+        match stream.read(&mut chunk).await {
+            Ok(0) | Err(_) => false,
+            Ok(n) => {
+                buf.extend_from_slice(&chunk[..n]);
+                true
+            }
+        }
+    }
+
+    fn remote(url: &str, address: Option<&str>) -> SignerSettings {
+        SignerSettings::Remote {
+            url: url.to_string(),
+            address: address.map(str::to_string),
+        }
+    }
+
+    fn tx() -> TypedTransaction {
+        TypedTransaction::Legacy(TransactionRequest::new().to(Address::zero()).value(1).nonce(0))
+    }
+
+    #[tokio::test]
+    async fn remote_signer_uses_the_first_account() {
+        let (url, requests) = mock_signer(json!("0x01")).await;
+        let signer = L1Signer::from_config(&remote(&url, None), 1).await.unwrap();
+
+        assert_eq!(signer.address(), ACCOUNT.parse::<Address>().unwrap());
+        assert_eq!(requests.lock().unwrap()[0]["method"], "eth_accounts");
+    }
+
+    #[tokio::test]
+    async fn remote_signer_uses_the_configured_address() {
+        let address = "0x00000000000000000000000000000000000000bb";
+        let (url, requests) = mock_signer(json!("0x01")).await;
+        let signer = L1Signer::from_config(&remote(&url, Some(address)), 1).await.unwrap();
+
+        assert_eq!(signer.address(), address.parse::<Address>().unwrap());
+        assert!(requests.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn remote_signer_accepts_a_bare_raw_transaction() {
+        let (url, requests) = mock_signer(json!("0xf86b01")).await;
+        let signer = L1Signer::from_config(&remote(&url, None), 1).await.unwrap();
+
+        assert_eq!(signer.sign(&tx()).await.unwrap(), Bytes::from(vec![0xf8, 0x6b, 0x01]));
+        let requests = requests.lock().unwrap();
+        let sign_request = requests.last().unwrap();
+        assert_eq!(sign_request["method"], "eth_signTransaction");
+        assert_eq!(sign_request["params"][0]["from"], ACCOUNT);
+    }
+
+    #[tokio::test]
+    async fn remote_signer_accepts_a_clef_answer() {
+        let (url, _) = mock_signer(json!({"raw": "0xf86b02", "tx": {}})).await;
+        let signer = L1Signer::from_config(&remote(&url, None), 1).await.unwrap();
+
+        assert_eq!(signer.sign(&tx()).await.unwrap(), Bytes::from(vec![0xf8, 0x6b, 0x02]));
+    }
+
+    #[tokio::test]
+    async fn remote_signer_rejects_an_unexpected_answer() {
+        let (url, _) = mock_signer(json!({"signature": "0x00"})).await;
+        let signer = L1Signer::from_config(&remote(&url, None), 1).await.unwrap();
+
+        assert!(signer.sign(&tx()).await.is_err());
+    }
+}
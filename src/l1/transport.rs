@@ -0,0 +1,376 @@
+//! A JSON-RPC transport over several L1 endpoints: reads every node answers alike need a quorum
+//! of matching answers, everything else fails over from the healthiest endpoint to the next.
+//! Each endpoint is reached over HTTP, WebSocket or IPC depending on its URL scheme.
+
+use async_trait::async_trait;
+use ethers::providers::{Http, HttpClientError, Ipc, IpcError, JsonRpcClient, Provider, ProviderError, Ws, WsClientError};
+use futures::future::join_all;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+
+const SCORE_MAX: i64 = 100;
+const SCORE_MIN: i64 = -100;
+const SCORE_SUCCESS: i64 = 1;
+const SCORE_FAILURE: i64 = -10;
+
+pub type L1Provider = Provider<MultiTransport>;
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct RpcSettings {
+    /// Endpoints used besides `web3_url`.
+    pub fallback_urls: Vec<String>,
+    /// Matching answers a read needs, capped at the number of endpoints. Only applies to reads
+    /// pinned to a block or that never change, others depend on how far each node got.
+    pub quorum: usize,
+}
+
+impl Default for RpcSettings {
+    fn default() -> Self {
+        Self {
+            fallback_urls: Vec::new(),
+            quorum: 1,
+        }
+    }
+}
+
+/// Builds a provider over `web3_url` followed by the configured fallbacks.
+pub async fn provider(web3_url: &str, config: &RpcSettings) -> Result<L1Provider, anyhow::Error> {
+    let urls: Vec<&str> = std::iter::once(web3_url)
+        .chain(config.fallback_urls.iter().map(String::as_str))
+        .collect();
+    Ok(Provider::new(MultiTransport::connect(&urls, config.quorum).await?))
+}
+
+/// Whether `url` is served over a transport that pushes subscriptions.
+pub fn is_pubsub(url: &str) -> bool {
+    !is_http(url)
+}
+
+fn is_http(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+pub(super) fn is_ws(url: &str) -> bool {
+    url.starts_with("ws://") || url.starts_with("wss://")
+}
+
+/// Error returned by a node, as opposed to failing to reach it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    pub data: Option<Value>,
+}
+
+#[derive(Debug)]
+pub enum MultiTransportError {
+    /// The agreed answer is an error, e.g. a reverting `eth_call`.
+    JsonRpc(RpcError),
+    NoQuorum {
+        method: String,
+        failures: Vec<String>,
+    },
+    Serde(serde_json::Error),
+}
+
+impl fmt::Display for MultiTransportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MultiTransportError::JsonRpc(e) => write!(f, "(code: {}, message: {}, data: {:?})", e.code, e.message, e.data),
+            MultiTransportError::NoQuorum { method, failures } => write!(f, "no quorum for {}: {:?}", method, failures),
+            MultiTransportError::Serde(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for MultiTransportError {}
+
+impl From<serde_json::Error> for MultiTransportError {
+    fn from(e: serde_json::Error) -> Self {
+        MultiTransportError::Serde(e)
+    }
+}
+
+impl From<MultiTransportError> for ProviderError {
+    fn from(e: MultiTransportError) -> Self {
+        ProviderError::JsonRpcClientError(Box::new(e))
+    }
+}
+
+/// Picked by URL scheme: `http(s)://`, `ws(s)://`, anything else is an IPC socket path.
+#[derive(Debug)]
+enum Client {
+    Http(Http),
+    Ws(Ws),
+    Ipc(Ipc),
+}
+
+impl Client {
+    async fn connect(url: &str) -> Result<Self, anyhow::Error> {
+        Ok(if is_http(url) {
+            Client::Http(url.parse::<Http>()?)
+        } else if is_ws(url) {
+            Client::Ws(Ws::connect(url).await?)
+        } else {
+            Client::Ipc(Ipc::connect(url).await?)
+        })
+    }
+}
+
+#[derive(Debug)]
+struct Endpoint {
+    url: String,
+    client: Client,
+    score: AtomicI64,
+}
+
+impl Endpoint {
+    /// `Ok(Err(_))` is an answer from the node, `Err(_)` means the node could not be reached.
+    async fn request(&self, method: &str, params: &Value) -> Result<Result<Value, RpcError>, String> {
+        let params = params.clone();
+        let res = match &self.client {
+            Client::Http(client) => match client.request::<_, Value>(method, params).await {
+                Ok(value) => Ok(Ok(value)),
+                Err(HttpClientError::JsonRpcError(e)) => Ok(Err(RpcError {
+                    code: e.code,
+                    message: e.message,
+                    data: e.data,
+                })),
+                Err(e) => Err(e.to_string()),
+            },
+            Client::Ws(client) => match client.request::<_, Value>(method, params).await {
+                Ok(value) => Ok(Ok(value)),
+                Err(WsClientError::JsonRpcError(e)) => Ok(Err(RpcError {
+                    code: e.code,
+                    message: e.message,
+                    data: e.data,
+                })),
+                Err(e) => Err(e.to_string()),
+            },
+            Client::Ipc(client) => match client.request::<_, Value>(method, params).await {
+                Ok(value) => Ok(Ok(value)),
+                Err(IpcError::JsonRpcError(e)) => Ok(Err(RpcError {
+                    code: e.code,
+                    message: e.message,
+                    data: e.data,
+                })),
+                Err(e) => Err(e.to_string()),
+            },
+        };
+        self.record(res.is_ok());
+        res.map_err(|e| format!("{}: {}", self.url, e))
+    }
+
+    fn record(&self, success: bool) {
+        let delta = if success { SCORE_SUCCESS } else { SCORE_FAILURE };
+        let score = (self.score.load(Ordering::Relaxed) + delta).clamp(SCORE_MIN, SCORE_MAX);
+        self.score.store(score, Ordering::Relaxed);
+        if !success {
+            log::warn!("l1 endpoint {} failed, health score {}", self.url, score);
+        }
+    }
+}
+
+/// Clones share the endpoints and their health scores.
+#[derive(Debug, Clone)]
+pub struct MultiTransport {
+    endpoints: Arc<Vec<Endpoint>>,
+    quorum: usize,
+}
+
+impl MultiTransport {
+    /// Connects to every endpoint it can, one that cannot be reached is left out with a warning.
+    /// Fails when fewer than `quorum` of them connect.
+    pub async fn connect(urls: &[&str], quorum: usize) -> Result<Self, anyhow::Error> {
+        if urls.is_empty() {
+            anyhow::bail!("no l1 endpoint configured");
+        }
+        let quorum = quorum.clamp(1, urls.len());
+        let mut endpoints = Vec::with_capacity(urls.len());
+        for url in urls {
+            match Client::connect(url).await {
+                Ok(client) => endpoints.push(Endpoint {
+                    url: url.to_string(),
+                    client,
+                    score: AtomicI64::new(0),
+                }),
+                Err(e) => log::warn!("l1 endpoint {} left out, cannot connect: {}", url, e),
+            }
+        }
+        if endpoints.len() < quorum {
+            anyhow::bail!(
+                "only {} of {} l1 endpoints connected, quorum is {}",
+                endpoints.len(),
+                urls.len(),
+                quorum
+            );
+        }
+        Ok(Self {
+            endpoints: Arc::new(endpoints),
+            quorum,
+        })
+    }
+
+    /// Endpoints from the healthiest to the least healthy, in configuration order on a tie.
+    fn by_health(&self) -> Vec<&Endpoint> {
+        let mut endpoints: Vec<&Endpoint> = self.endpoints.iter().collect();
+        endpoints.sort_by_key(|e| -e.score.load(Ordering::Relaxed));
+        endpoints
+    }
+
+    async fn failover(&self, method: &str, params: &Value) -> Result<Result<Value, RpcError>, MultiTransportError> {
+        let mut failures = Vec::new();
+        for endpoint in self.by_health() {
+            match endpoint.request(method, params).await {
+                Ok(answer) => return Ok(answer),
+                Err(e) => failures.push(e),
+            }
+        }
+        Err(MultiTransportError::NoQuorum {
+            method: method.to_string(),
+            failures,
+        })
+    }
+
+    async fn quorum(&self, method: &str, params: &Value) -> Result<Result<Value, RpcError>, MultiTransportError> {
+        if self.quorum == 1 {
+            return self.failover(method, params).await;
+        }
+
+        let results = join_all(self.endpoints.iter().map(|e| e.request(method, params))).await;
+        let mut failures = Vec::new();
+        let mut answers: Vec<Result<Value, RpcError>> = Vec::new();
+        for res in results {
+            match res {
+                Ok(answer) => answers.push(answer),
+                Err(e) => failures.push(e),
+            }
+        }
+
+        // nodes are rarely at the exact same height, so agree on the lowest one
+        if method == "eth_blockNumber" && answers.len() >= self.quorum {
+            if let Some(lowest) = answers
+                .iter()
+                .filter_map(|a| a.as_ref().ok())
+                .filter_map(|v| {
+                    v.as_str()
+                        .and_then(|s| u64::from_str_radix(s.trim_start_matches("0x"), 16).ok())
+                        .map(|n| (n, v))
+                })
+                .min_by_key(|(n, _)| *n)
+            {
+                return Ok(Ok(lowest.1.clone()));
+            }
+        }
+
+        for answer in &answers {
+            if answers.iter().filter(|a| *a == answer).count() >= self.quorum {
+                return Ok(answer.clone());
+            }
+        }
+        failures.extend(answers.iter().map(|a| format!("{:?}", a)));
+        Err(MultiTransportError::NoQuorum {
+            method: method.to_string(),
+            failures,
+        })
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for MultiTransport {
+    type Error = MultiTransportError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: fmt::Debug + Serialize + Send + Sync,
+        R: DeserializeOwned,
+    {
+        let params = serde_json::to_value(params)?;
+        let answer = if agreed_on(method, &params) {
+            self.quorum(method, &params).await?
+        } else {
+            self.failover(method, &params).await?
+        };
+        match answer {
+            Ok(value) => Ok(serde_json::from_value(value)?),
+            Err(e) => Err(MultiTransportError::JsonRpc(e)),
+        }
+    }
+}
+
+/// Whether synced nodes all answer `method` alike: it never changes, or it is pinned to a block
+/// number or hash. Reads at `latest` or `pending` differ while nodes are a block apart.
+fn agreed_on(method: &str, params: &Value) -> bool {
+    let block = match method {
+        // nodes disagree on it too, `quorum` settles on the lowest answer
+        "eth_blockNumber" | "eth_chainId" | "eth_getBlockByHash" => return true,
+        "eth_getBlockByNumber" => params.get(0),
+        "eth_getBalance" | "eth_getCode" | "eth_getStorageAt" | "eth_getTransactionCount" | "eth_call" => {
+            params.as_array().filter(|params| params.len() > 1).and_then(|params| params.last())
+        }
+        "eth_getLogs" => {
+            return params.get(0).map_or(false, |filter| {
+                filter.get("blockHash").is_some() || (is_pinned(filter.get("fromBlock")) && is_pinned(filter.get("toBlock")))
+            })
+        }
+        _ => return false,
+    };
+    is_pinned(block)
+}
+
+/// Whether a block parameter names one block, by number or by hash as in EIP-1898.
+fn is_pinned(block: Option<&Value>) -> bool {
+    match block {
+        Some(Value::String(tag)) => tag.starts_with("0x") || tag == "earliest",
+        Some(Value::Object(block)) => block.contains_key("blockHash") || block.contains_key("blockNumber"),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn pinned_reads_need_quorum() {
+        assert!(agreed_on("eth_getBalance", &json!(["0x01", "0x10"])));
+        assert!(agreed_on("eth_call", &json!([{"to": "0x01"}, {"blockHash": "0xab"}])));
+        assert!(agreed_on("eth_getBlockByNumber", &json!(["0x10", false])));
+        assert!(agreed_on("eth_getLogs", &json!([{"fromBlock": "0x1", "toBlock": "0x10"}])));
+        assert!(agreed_on("eth_chainId", &json!([])));
+    }
+
+    #[test]
+    fn head_dependent_reads_fail_over() {
+        assert!(!agreed_on("eth_getBalance", &json!(["0x01", "latest"])));
+        assert!(!agreed_on("eth_getTransactionCount", &json!(["0x01", "pending"])));
+        assert!(!agreed_on("eth_getBlockByNumber", &json!(["latest", false])));
+        assert!(!agreed_on("eth_call", &json!([{"to": "0x01"}])));
+        assert!(!agreed_on("eth_getLogs", &json!([{"fromBlock": "0x1", "toBlock": "latest"}])));
+        assert!(!agreed_on("eth_gasPrice", &json!([])));
+        assert!(!agreed_on("eth_estimateGas", &json!([{"to": "0x01"}])));
+        assert!(!agreed_on("eth_getTransactionReceipt", &json!(["0xab"])));
+    }
+
+    #[test]
+    fn writes_fail_over() {
+        assert!(!agreed_on("eth_sendRawTransaction", &json!(["0x00"])));
+    }
+
+    #[tokio::test]
+    async fn unreachable_endpoints_are_left_out() {
+        let urls = ["http://127.0.0.1:8545", "ws://127.0.0.1:1", "/nonexistent/geth.ipc"];
+        let transport = MultiTransport::connect(&urls, 1).await.unwrap();
+        assert_eq!(transport.endpoints.len(), 1);
+        assert_eq!(transport.endpoints[0].url, urls[0]);
+
+        assert!(MultiTransport::connect(&urls, 2).await.is_err());
+        assert!(MultiTransport::connect(&[], 1).await.is_err());
+    }
+}
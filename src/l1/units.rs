@@ -0,0 +1,74 @@
+use crate::storage::DecimalDbType;
+use anyhow::anyhow;
+use ethers::types::U256;
+
+/// Scales a decimal amount up to the token's smallest unit, e.g. ETH to wei.
+pub fn to_base_units(amount: DecimalDbType, decimals: u32) -> Result<U256, anyhow::Error> {
+    let amount = amount.normalize();
+    if amount.is_sign_negative() || amount.scale() > decimals {
+        return Err(anyhow!("invalid amount {} for {} decimals", amount, decimals));
+    }
+    let mantissa = U256::from_dec_str(&amount.mantissa().to_string())?;
+    Ok(mantissa * U256::exp10((decimals - amount.scale()) as usize))
+}
+
+/// Scales an amount of the token's smallest unit down to a decimal, e.g. wei to ETH.
+pub fn from_base_units(amount: U256, decimals: u32) -> Result<DecimalDbType, anyhow::Error> {
+    if amount > U256::from(i128::MAX as u128) {
+        return Err(anyhow!("amount {} out of range", amount));
+    }
+    let mantissa = amount.as_u128() as i128;
+    DecimalDbType::try_from_i128_with_scale(mantissa, decimals)
+        .map(|d| d.normalize())
+        .map_err(|e| anyhow!("amount {} with {} decimals: {:?}", amount, decimals, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn dec(s: &str) -> DecimalDbType {
+        DecimalDbType::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn to_base_units_scales_up() {
+        assert_eq!(to_base_units(dec("1.5"), 18).unwrap(), U256::from(15) * U256::exp10(17));
+        assert_eq!(to_base_units(dec("10000"), 6).unwrap(), U256::from(10_000_000_000u64));
+        assert_eq!(to_base_units(dec("0"), 18).unwrap(), U256::zero());
+    }
+
+    #[test]
+    fn to_base_units_ignores_trailing_zeros() {
+        assert_eq!(to_base_units(dec("1.500000000"), 6).unwrap(), U256::from(1_500_000));
+    }
+
+    #[test]
+    fn to_base_units_rejects_excess_precision() {
+        assert!(to_base_units(dec("0.0000001"), 6).is_err());
+    }
+
+    #[test]
+    fn to_base_units_rejects_negative() {
+        assert!(to_base_units(dec("-1"), 18).is_err());
+    }
+
+    #[test]
+    fn from_base_units_scales_down() {
+        assert_eq!(from_base_units(U256::from(15) * U256::exp10(17), 18).unwrap(), dec("1.5"));
+        assert_eq!(from_base_units(U256::from(1), 18).unwrap(), dec("0.000000000000000001"));
+        assert_eq!(from_base_units(U256::from(1_500_000), 6).unwrap(), dec("1.5"));
+    }
+
+    #[test]
+    fn from_base_units_rejects_out_of_range() {
+        assert!(from_base_units(U256::MAX, 18).is_err());
+    }
+
+    #[test]
+    fn round_trips() {
+        let wei = U256::from_dec_str("123456789012345678901").unwrap();
+        assert_eq!(to_base_units(from_base_units(wei, 18).unwrap(), 18).unwrap(), wei);
+    }
+}
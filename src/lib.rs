@@ -2,5 +2,8 @@ pub mod block_submitter;
 pub mod contracts;
 pub mod faucet;
 pub mod grpc_client;
+pub mod l1;
 pub mod mq;
 pub mod storage;
+pub mod tele_in;
+pub mod tele_out;
@@ -1,9 +1,18 @@
+use fluidex_common::rdkafka;
+use messages::WrappedMessage;
+use rdkafka::consumer::{BaseConsumer, Consumer};
+use rdkafka::message::{BorrowedMessage, Message};
+use std::time::Duration;
+
 pub mod messages {
+    use crate::storage::DecimalDbType;
     use serde::{Deserialize, Serialize};
 
     #[derive(Debug)]
     pub enum WrappedMessage {
         User(UserMessage),
+        Transfer(TransferMessage),
+        Balance(BalanceMessage),
     }
 
     #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -12,4 +21,122 @@ pub mod messages {
         pub l1_address: String,
         pub l2_pubkey: String,
     }
+
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub struct TransferMessage {
+        pub time: f64,
+        pub user_from: u32,
+        pub user_to: u32,
+        pub asset: String,
+        pub amount: DecimalDbType,
+    }
+
+    /// A balance change applied by the matchengine, `detail` is the one passed with the update.
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub struct BalanceMessage {
+        pub timestamp: f64,
+        pub user_id: u32,
+        pub asset: String,
+        pub business: String,
+        pub change: DecimalDbType,
+        pub balance: DecimalDbType,
+        pub detail: String,
+    }
+}
+
+/// Messages buffered between a Kafka consumer thread and its handler, the consumer blocks once they are full.
+pub const MSG_CHANNEL_SIZE: usize = 1024;
+
+const UNIFY_TOPIC: &str = "unifyevents";
+/// How long a poll of the consumer waits for a message.
+const POLL_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Turns a `unifyevents` message of the `key` type into the one a service handles, `None` for
+/// the ones it ignores.
+pub type Decoder = fn(key: &str, payload: &str) -> Result<Option<WrappedMessage>, anyhow::Error>;
+
+/// A message together with its position in the topic, which identifies it across redeliveries.
+#[derive(Debug)]
+pub struct PositionedMessage {
+    pub partition: i32,
+    pub offset: i64,
+    pub message: WrappedMessage,
+}
+
+/// Consumes `unifyevents` as `group_id` on a thread of its own and hands the messages `decode`
+/// keeps to `sender`. Stops once the receiving end is dropped.
+pub fn load_msgs_from_mq(
+    brokers: &str,
+    group_id: &str,
+    decode: Decoder,
+    sender: tokio::sync::mpsc::Sender<PositionedMessage>,
+) -> Option<std::thread::JoinHandle<anyhow::Result<()>>> {
+    let brokers = brokers.to_owned();
+    let group_id = group_id.to_owned();
+    Some(std::thread::spawn(move || {
+        let consumer: BaseConsumer = rdkafka::config::ClientConfig::new()
+            .set("bootstrap.servers", brokers)
+            .set("group.id", &group_id)
+            .set("enable.partition.eof", "false")
+            .set("session.timeout.ms", "6000")
+            .set("enable.auto.commit", "true")
+            .set("auto.offset.reset", "earliest")
+            .create()?;
+        consumer.subscribe(&[UNIFY_TOPIC])?;
+
+        loop {
+            let msg = match consumer.poll(POLL_TIMEOUT) {
+                Some(Ok(msg)) => msg,
+                Some(Err(e)) => {
+                    log::error!("Kafka consumer error: {}", e);
+                    continue;
+                }
+                None => continue,
+            };
+            let message = match positioned(&msg, decode) {
+                Some(message) => message,
+                None => continue,
+            };
+            // blocks the consumer while the handler is behind, this thread runs no async tasks
+            if sender.blocking_send(message).is_err() {
+                log::warn!("{} message handler is gone, stopping its consumer", group_id);
+                return Ok(());
+            }
+        }
+    }))
+}
+
+/// Decodes `msg` with its position, `None` if it is skipped.
+fn positioned(msg: &BorrowedMessage<'_>, decode: Decoder) -> Option<PositionedMessage> {
+    let (msg_type, msg_payload) = match (msg.key().map(std::str::from_utf8), msg.payload().map(std::str::from_utf8)) {
+        (Some(Ok(msg_type)), Some(Ok(msg_payload))) => (msg_type, msg_payload),
+        _ => {
+            log::warn!(
+                "skip message at partition {}, offset {}: no UTF-8 key or payload",
+                msg.partition(),
+                msg.offset()
+            );
+            return None;
+        }
+    };
+    // one bad payload must not stop the consumer, and with it the service
+    let message = match decode(msg_type, msg_payload) {
+        Ok(message) => message?,
+        Err(e) => {
+            log::error!(
+                "skip {} message at partition {}, offset {}: {:?}",
+                msg_type,
+                msg.partition(),
+                msg.offset(),
+                e
+            );
+            return None;
+        }
+    };
+
+    Some(PositionedMessage {
+        partition: msg.partition(),
+        offset: msg.offset(),
+        message,
+    })
 }
@@ -6,3 +6,89 @@ pub type DbErrType = sqlx::Error;
 
 pub type TimestampDbType = chrono::NaiveDateTime;
 pub type DecimalDbType = rust_decimal::Decimal;
+
+/// Runs `migrator`, tracking applied migrations in `table` rather than sqlx's shared
+/// `_sqlx_migrations`, which rejects migrations it does not know about. This lets a service
+/// live in a database that another service or the rollup itself migrates. Migrations a
+/// database already records in `_sqlx_migrations` are taken over rather than applied again.
+pub async fn run_migrations(pool: &PoolType, migrator: &sqlx::migrate::Migrator, table: &str) -> anyhow::Result<()> {
+    use sqlx::Executor;
+
+    let stmt = format!(
+        "create table if not exists {} (
+            version BIGINT PRIMARY KEY,
+            description TEXT NOT NULL,
+            checksum BYTEA NOT NULL,
+            installed_on TIMESTAMPTZ NOT NULL DEFAULT now()
+        )",
+        table
+    );
+    pool.execute(stmt.as_str()).await?;
+
+    let query = format!("select version, checksum from {}", table);
+    let mut applied: std::collections::HashMap<i64, Vec<u8>> = sqlx::query_as(&query).fetch_all(pool).await?.into_iter().collect();
+    if applied.is_empty() {
+        applied = adopt_sqlx_migrations(pool, migrator, table).await?;
+    }
+
+    for migration in migrator.iter() {
+        match applied.get(&migration.version) {
+            Some(checksum) if checksum.as_slice() != &*migration.checksum => {
+                anyhow::bail!("migration {} was modified after it had been applied", migration.version)
+            }
+            Some(_) => continue,
+            None => {
+                let mut tx = pool.begin().await?;
+                tx.execute(&*migration.sql).await?;
+                let stmt = format!("insert into {} (version, description, checksum) values ($1, $2, $3)", table);
+                sqlx::query(&stmt)
+                    .bind(migration.version)
+                    .bind(&*migration.description)
+                    .bind(&*migration.checksum)
+                    .execute(&mut tx)
+                    .await?;
+                tx.commit().await?;
+                log::info!("applied migration {} {}", migration.version, migration.description);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Copies the entries of `migrator` that sqlx's own `_sqlx_migrations` records as applied into `table`.
+async fn adopt_sqlx_migrations(
+    pool: &PoolType,
+    migrator: &sqlx::migrate::Migrator,
+    table: &str,
+) -> anyhow::Result<std::collections::HashMap<i64, Vec<u8>>> {
+    let exists: bool = sqlx::query_scalar("select to_regclass('_sqlx_migrations') is not null")
+        .fetch_one(pool)
+        .await?;
+    if !exists {
+        return Ok(Default::default());
+    }
+
+    let legacy: std::collections::HashMap<i64, Vec<u8>> = sqlx::query_as("select version, checksum from _sqlx_migrations where success")
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .collect();
+    let mut adopted = std::collections::HashMap::new();
+    for migration in migrator.iter() {
+        if legacy.get(&migration.version).map(Vec::as_slice) == Some(&*migration.checksum) {
+            let stmt = format!("insert into {} (version, description, checksum) values ($1, $2, $3)", table);
+            sqlx::query(&stmt)
+                .bind(migration.version)
+                .bind(&*migration.description)
+                .bind(&*migration.checksum)
+                .execute(pool)
+                .await?;
+            adopted.insert(migration.version, migration.checksum.to_vec());
+        }
+    }
+    if !adopted.is_empty() {
+        log::info!("took over {} migrations from _sqlx_migrations into {}", adopted.len(), table);
+    }
+    Ok(adopted)
+}
@@ -0,0 +1,56 @@
+use crate::l1::RpcSettings;
+use serde::Deserialize;
+use std::num::NonZeroU64;
+use std::time::Duration;
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct Settings {
+    pub brokers: String,
+    pub db: String,
+    pub grpc_upstream: String,
+    pub web3_url: String,
+    #[serde(default)]
+    pub rpc: RpcSettings,
+    pub contract_address: String,
+    pub contract_abi_file_path: String,
+    pub confirmations: u64,
+    /// L1 block to start from when nothing has been synced yet, e.g. the contract deployment.
+    pub start_block: u64,
+    pub poll_interval: u64,
+    #[serde(default = "default_max_block_range")]
+    pub max_block_range: NonZeroU64,
+    #[serde(default = "default_deposit_event")]
+    pub deposit_event: String,
+    pub tokens: Vec<TokenSettings>,
+    /// Failed gRPC credits after which a deposit is marked `failed` for manual handling.
+    #[serde(default = "default_max_credit_attempts")]
+    pub max_credit_attempts: i32,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct TokenSettings {
+    /// Token id assigned by the FluiDex contract.
+    pub token_id: u16,
+    /// Matchengine asset name.
+    pub asset: String,
+    pub decimals: u32,
+}
+
+fn default_max_block_range() -> NonZeroU64 {
+    NonZeroU64::new(1000).unwrap()
+}
+
+fn default_max_credit_attempts() -> i32 {
+    10
+}
+
+fn default_deposit_event() -> String {
+    "Deposit".to_string()
+}
+
+impl Settings {
+    /// Converts `self.poll_interval` into `Duration`.
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_secs(self.poll_interval)
+    }
+}
@@ -0,0 +1,335 @@
+use crate::contracts;
+use crate::grpc_client::{self, GrpcClient};
+use crate::l1::{transport, units, L1Provider};
+use crate::storage::{DbType, PoolType};
+use crate::tele_in::config::TokenSettings;
+use crate::tele_in::{storage::models, Settings};
+use anyhow::anyhow;
+use ethers::abi::{Event, RawLog, Token};
+use ethers::prelude::*;
+use ethers::utils::keccak256;
+use std::collections::HashMap;
+use std::time::Duration;
+
+const PARAM_TOKEN_ID: &str = "tokenId";
+const PARAM_TO: &str = "to";
+const PARAM_AMOUNT: &str = "amount";
+
+/// Watches the FluiDex contract's deposit events and credits the depositing users on L2
+/// once the deposits have `confirmations`.
+#[derive(Debug)]
+pub struct DepositWatcher {
+    connpool: PoolType,
+    provider: L1Provider,
+    contract_address: Address,
+    event: Event,
+    grpc_client: GrpcClient,
+    tokens: HashMap<u16, TokenSettings>,
+    confirmations: u64,
+    start_block: u64,
+    max_block_range: u64,
+    max_credit_attempts: i32,
+    poll_interval: Duration,
+}
+
+impl DepositWatcher {
+    pub async fn from_config_with_pool(config: &Settings, connpool: PoolType) -> Result<Self, anyhow::Error> {
+        let abi = contracts::get_abi(&config.contract_abi_file_path)?;
+
+        Ok(Self {
+            connpool,
+            provider: transport::provider(&config.web3_url, &config.rpc).await?,
+            contract_address: config.contract_address.parse::<Address>()?,
+            event: abi.event(&config.deposit_event)?.clone(),
+            grpc_client: GrpcClient {
+                upstream: config.grpc_upstream.clone(),
+            },
+            tokens: config.tokens.iter().map(|t| (t.token_id, t.clone())).collect(),
+            confirmations: config.confirmations,
+            start_block: config.start_block,
+            max_block_range: config.max_block_range.get(),
+            max_credit_attempts: config.max_credit_attempts,
+            poll_interval: config.poll_interval(),
+        })
+    }
+
+    pub async fn run(&self) {
+        let mut timer = tokio::time::interval(self.poll_interval);
+        loop {
+            timer.tick().await;
+            log::debug!("ticktock!");
+
+            if let Err(e) = self.sync().await {
+                log::error!("sync deposits: {}", e);
+            };
+            if let Err(e) = self.credit_observed().await {
+                log::error!("credit deposits: {}", e);
+            };
+        }
+    }
+
+    /// Records the deposit events of the L1 blocks since the last sync.
+    async fn sync(&self) -> Result<(), anyhow::Error> {
+        let query = format!(
+            "select last_synced_l1_block from {} where id = 1",
+            models::tablenames::TELE_IN_PROGRESS
+        );
+        let synced: Option<i64> = sqlx::query_scalar(&query).fetch_one(&self.connpool).await?;
+        let from = synced.map_or(self.start_block, |b| b as u64 + 1);
+        let head = self.provider.get_block_number().await?.as_u64();
+        let to = head.saturating_sub(self.confirmations).min(from + self.max_block_range - 1);
+        if to < from {
+            return Ok(());
+        }
+
+        let filter = Filter::new()
+            .address(self.contract_address)
+            .topic0(self.event.signature())
+            .from_block(from)
+            .to_block(to);
+        let logs = self.provider.get_logs(&filter).await?;
+
+        let mut db_tx = self.connpool.begin().await?;
+        for log in logs {
+            match self.decode(&log) {
+                Ok(deposit) => self.record(&mut db_tx, &deposit).await?,
+                // nothing to credit from it, and it must not hold back the ones after it
+                Err(e) => log::error!(
+                    "skip undecodable {} log {:?}:{:?}: {}",
+                    self.event.name,
+                    log.transaction_hash,
+                    log.log_index,
+                    e
+                ),
+            }
+        }
+        let stmt = format!(
+            "update {} set last_synced_l1_block = $1, updated_time = CURRENT_TIMESTAMP where id = 1",
+            models::tablenames::TELE_IN_PROGRESS
+        );
+        sqlx::query(&stmt).bind(to as i64).execute(&mut db_tx).await?;
+        db_tx.commit().await?;
+        Ok(())
+    }
+
+    fn decode(&self, log: &Log) -> Result<Deposit, anyhow::Error> {
+        let tx_hash = log.transaction_hash.ok_or_else(|| anyhow!("deposit log without tx hash"))?;
+        let log_index = log.log_index.ok_or_else(|| anyhow!("deposit log without log index"))?;
+        let parsed = self.event.parse_log(RawLog {
+            topics: log.topics.clone(),
+            data: log.data.to_vec(),
+        })?;
+        let param = |name: &str| {
+            parsed
+                .params
+                .iter()
+                .find(|p| p.name == name)
+                .map(|p| p.value.clone())
+                .ok_or_else(|| anyhow!("{} event without {}", self.event.name, name))
+        };
+        let (token_id, to, amount) = match (param(PARAM_TOKEN_ID)?, param(PARAM_TO)?, param(PARAM_AMOUNT)?) {
+            (Token::Uint(token_id), Token::FixedBytes(to), Token::Uint(amount)) => (token_id, to, amount),
+            params => return Err(anyhow!("unexpected {} params {:?}", self.event.name, params)),
+        };
+        let block_number = log.block_number.ok_or_else(|| anyhow!("deposit log without block number"))?;
+        Ok(Deposit {
+            tx_hash,
+            log_index: checked(log_index).ok_or_else(|| anyhow!("deposit log index {} out of range", log_index))?,
+            block_number: i64::try_from(block_number.as_u64())?,
+            token_id,
+            to,
+            amount,
+        })
+    }
+
+    async fn record(&self, db_tx: &mut sqlx::Transaction<'_, DbType>, deposit: &Deposit) -> Result<(), anyhow::Error> {
+        let token_id: Option<u16> = checked(deposit.token_id);
+        let (asset, amount, error) = match token_id.and_then(|token_id| self.tokens.get(&token_id)) {
+            Some(token) => match units::from_base_units(deposit.amount, token.decimals) {
+                Ok(amount) => (Some(token.asset.clone()), Some(amount), None),
+                Err(e) => (Some(token.asset.clone()), None, Some(e.to_string())),
+            },
+            None => (None, None, Some(format!("unknown token id {}", deposit.token_id))),
+        };
+        let status = match error {
+            Some(_) => models::DepositStatus::Rejected,
+            None => models::DepositStatus::Observed,
+        };
+
+        let stmt = format!(
+            "insert into {} (l1_tx_hash, l1_log_index, l1_block_number, business_id, token_id, to_pubkey, asset, amount, status, last_error)
+            values ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            on conflict (l1_tx_hash, l1_log_index) do nothing",
+            models::tablenames::DEPOSIT_TX
+        );
+        sqlx::query(&stmt)
+            .bind(format!("{:#x}", deposit.tx_hash))
+            .bind(deposit.log_index)
+            .bind(deposit.block_number)
+            .bind(business_id(deposit.tx_hash, deposit.log_index as u64))
+            .bind(token_id.map(i32::from))
+            .bind(format!("0x{}", hex::encode(&deposit.to)))
+            .bind(asset)
+            .bind(amount)
+            .bind(status)
+            .bind(error)
+            .execute(&mut *db_tx)
+            .await?;
+        Ok(())
+    }
+
+    /// Credits recorded deposits, retrying is safe as the matchengine refuses a repeated `business_id`.
+    async fn credit_observed(&self) -> Result<(), anyhow::Error> {
+        #[derive(sqlx::FromRow)]
+        struct Credit {
+            #[sqlx(flatten)]
+            deposit: models::DepositTx,
+            user_id: Option<i32>,
+        }
+
+        let query = format!(
+            "select d.*, u.user_id
+            from {} d
+                     left join {} u on d.to_pubkey = u.l2_pubkey
+            where d.status = $1
+            order by d.id",
+            models::tablenames::DEPOSIT_TX,
+            models::tablenames::TELE_IN_USER
+        );
+        let credits: Vec<Credit> = sqlx::query_as(&query)
+            .bind(models::DepositStatus::Observed)
+            .fetch_all(&self.connpool)
+            .await?;
+
+        for Credit { deposit, user_id } in credits {
+            let user_id = match user_id {
+                Some(user_id) => user_id,
+                None => {
+                    // the user registration may not have been consumed yet
+                    log::warn!("deposit {} to unknown l2 pubkey {}", deposit.id, deposit.to_pubkey);
+                    continue;
+                }
+            };
+
+            if let Err(e) = self.grpc_client.deposit(user_id as u32, &deposit).await {
+                if !grpc_client::is_duplicate(&e) {
+                    self.mark_credit_failed(&deposit, &e.to_string()).await?;
+                    log::error!(
+                        "grpc_client credit deposit {} (attempt {}): {:?}",
+                        deposit.id,
+                        deposit.attempts + 1,
+                        e
+                    );
+                    continue;
+                }
+                // credited before, the status update got lost
+                log::warn!("deposit {} already credited: {}", deposit.id, e);
+            }
+
+            let stmt = format!("update {} set status = $1 where id = $2", models::tablenames::DEPOSIT_TX);
+            sqlx::query(&stmt)
+                .bind(models::DepositStatus::Credited)
+                .bind(deposit.id)
+                .execute(&self.connpool)
+                .await?;
+            log::info!(
+                "({:?}) amount of asset ({:?}) deposited to user {} in tx {}",
+                deposit.amount,
+                deposit.asset,
+                user_id,
+                deposit.l1_tx_hash
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Counts a failed credit, the deposit is given up on once it used up its attempts.
+    async fn mark_credit_failed(&self, deposit: &models::DepositTx, error: &str) -> Result<(), anyhow::Error> {
+        let stmt = format!(
+            "update {} set attempts = attempts + 1, last_error = $1,
+                status = case when attempts + 1 >= $2 then $3 else status end
+            where id = $4",
+            models::tablenames::DEPOSIT_TX
+        );
+        sqlx::query(&stmt)
+            .bind(error)
+            .bind(self.max_credit_attempts)
+            .bind(models::DepositStatus::Failed)
+            .bind(deposit.id)
+            .execute(&self.connpool)
+            .await?;
+        if deposit.attempts + 1 >= self.max_credit_attempts {
+            log::error!("deposit {} failed after {} attempts: {}", deposit.id, deposit.attempts + 1, error);
+        }
+        Ok(())
+    }
+}
+
+/// A deposit event decoded from its log.
+#[derive(Debug)]
+struct Deposit {
+    tx_hash: H256,
+    log_index: i64,
+    block_number: i64,
+    /// As emitted, a token id beyond `u16` is recorded as rejected.
+    token_id: U256,
+    to: Vec<u8>,
+    amount: U256,
+}
+
+/// Narrows `value` to `T`, `None` if it does not fit.
+fn checked<T: TryFrom<u64>>(value: U256) -> Option<T> {
+    u64::try_from(value).ok().and_then(|value| T::try_from(value).ok())
+}
+
+/// Deterministic `business_id` of a deposit, so that a replayed log credits nothing twice.
+fn business_id(tx_hash: H256, log_index: u64) -> i64 {
+    let mut preimage = tx_hash.as_bytes().to_vec();
+    preimage.extend_from_slice(&log_index.to_be_bytes());
+    let hash = keccak256(&preimage);
+    let mut id = [0u8; 8];
+    id.copy_from_slice(&hash[..8]);
+    // keep it positive so it fits BIGINT as well as the matchengine's u64
+    (u64::from_be_bytes(id) >> 1) as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn business_id_is_deterministic_and_positive() {
+        let tx_hash = H256::repeat_byte(0xab);
+        let id = business_id(tx_hash, 3);
+        assert_eq!(id, business_id(tx_hash, 3));
+        assert!(id >= 0);
+    }
+
+    #[test]
+    fn business_id_tells_logs_apart() {
+        let tx_hash = H256::repeat_byte(0xab);
+        assert_ne!(business_id(tx_hash, 0), business_id(tx_hash, 1));
+        assert_ne!(business_id(tx_hash, 0), business_id(H256::repeat_byte(0xcd), 0));
+    }
+
+    #[test]
+    fn business_id_uses_the_hash_prefix() {
+        let tx_hash = H256::zero();
+        let mut preimage = tx_hash.as_bytes().to_vec();
+        preimage.extend_from_slice(&0u64.to_be_bytes());
+        let hash = keccak256(&preimage);
+        let mut prefix = [0u8; 8];
+        prefix.copy_from_slice(&hash[..8]);
+        assert_eq!(business_id(tx_hash, 0), (u64::from_be_bytes(prefix) >> 1) as i64);
+    }
+
+    #[test]
+    fn checked_rejects_what_does_not_fit() {
+        assert_eq!(checked::<u16>(U256::from(65535)), Some(65535));
+        assert_eq!(checked::<u16>(U256::from(65536)), None);
+        assert_eq!(checked::<i64>(U256::from(u64::MAX)), None);
+        assert_eq!(checked::<u64>(U256::MAX), None);
+        assert_eq!(checked::<i64>(U256::from(7)), Some(7));
+    }
+}
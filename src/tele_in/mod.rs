@@ -0,0 +1,10 @@
+pub mod config;
+pub mod deposit_watcher;
+pub mod storage;
+pub mod user_syncer;
+
+pub use config::Settings;
+pub use deposit_watcher::DepositWatcher;
+pub use user_syncer::UserSyncer;
+
+mod msg;
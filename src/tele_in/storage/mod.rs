@@ -0,0 +1,14 @@
+use crate::storage::{run_migrations, PoolOptions, PoolType};
+use crate::tele_in::Settings;
+
+pub mod models;
+
+static MIGRATOR: sqlx::migrate::Migrator = sqlx::migrate!("./migrations/tele_in");
+
+pub async fn from_config(config: &Settings) -> anyhow::Result<PoolType> {
+    let db_pool = PoolOptions::new().connect(&config.db).await?;
+
+    run_migrations(&db_pool, &MIGRATOR, "_tele_in_migrations").await?;
+
+    Ok(db_pool)
+}
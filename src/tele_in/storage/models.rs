@@ -0,0 +1,37 @@
+use crate::storage::{DecimalDbType, TimestampDbType};
+use serde::Serialize;
+
+pub mod tablenames {
+    pub const TELE_IN_USER: &str = "tele_in_user";
+    pub const DEPOSIT_TX: &str = "deposit_tx";
+    pub const TELE_IN_PROGRESS: &str = "tele_in_progress";
+}
+
+#[derive(sqlx::Type, Debug, Clone, Serialize)]
+#[sqlx(type_name = "deposit_status", rename_all = "snake_case")]
+pub enum DepositStatus {
+    Observed,
+    Credited,
+    /// Credit given up on after `max_credit_attempts`.
+    Failed,
+    /// Never creditable, e.g. of an unknown token.
+    Rejected,
+}
+
+#[derive(sqlx::FromRow, Debug, Clone, Serialize)]
+pub struct DepositTx {
+    pub id: i32,
+    pub l1_tx_hash: String,
+    pub l1_log_index: i64,
+    pub l1_block_number: i64,
+    pub business_id: i64,
+    pub token_id: Option<i32>,
+    pub to_pubkey: String,
+    pub asset: Option<String>,
+    pub amount: Option<DecimalDbType>,
+    pub status: DepositStatus,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub created_time: TimestampDbType,
+    pub updated_time: TimestampDbType,
+}
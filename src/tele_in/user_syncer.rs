@@ -0,0 +1,55 @@
+use super::msg;
+use crate::mq::messages::{UserMessage, WrappedMessage};
+use crate::mq::{load_msgs_from_mq, PositionedMessage, MSG_CHANNEL_SIZE};
+use crate::storage::PoolType;
+use crate::tele_in::storage::models;
+use crate::tele_in::Settings;
+
+/// Keeps the L2 pubkey to user id mapping deposits are credited by.
+#[derive(Debug)]
+pub struct UserSyncer {
+    brokers: String,
+    connpool: PoolType,
+}
+
+impl UserSyncer {
+    pub fn from_config_with_pool(config: &Settings, connpool: PoolType) -> Self {
+        Self {
+            brokers: config.brokers.to_owned(),
+            connpool,
+        }
+    }
+
+    pub async fn run(&self) {
+        let (msg_sender, mut msg_receiver) = tokio::sync::mpsc::channel(MSG_CHANNEL_SIZE);
+        let loader_thread = load_msgs_from_mq(&self.brokers, msg::MSG_CONSUMER_GROUP, msg::decode, msg_sender);
+
+        while let Some(PositionedMessage { message, .. }) = msg_receiver.recv().await {
+            match message {
+                WrappedMessage::User(user) => {
+                    if let Err(e) = self.save_user(&user).await {
+                        log::error!("save user {:?}, error: {:?}", user.user_id, e);
+                    }
+                }
+                WrappedMessage::Transfer(_) | WrappedMessage::Balance(_) => {}
+            }
+        }
+
+        loader_thread.map(|h| h.join().expect("loader thread failed"));
+    }
+
+    async fn save_user(&self, user: &UserMessage) -> Result<(), anyhow::Error> {
+        let stmt = format!(
+            "insert into {} (user_id, l1_address, l2_pubkey) values ($1, $2, lower($3))
+            on conflict (user_id) do update set l1_address = excluded.l1_address, l2_pubkey = excluded.l2_pubkey",
+            models::tablenames::TELE_IN_USER
+        );
+        sqlx::query(&stmt)
+            .bind(user.user_id)
+            .bind(&user.l1_address)
+            .bind(&user.l2_pubkey)
+            .execute(&self.connpool)
+            .await?;
+        Ok(())
+    }
+}
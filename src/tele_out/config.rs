@@ -0,0 +1,42 @@
+use crate::l1::{FeeSettings, RpcSettings, SignerSettings};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::Duration;
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct Settings {
+    pub brokers: String,
+    pub db: String,
+    /// L2 account that users withdraw to, funds are paid out from the L1 `signer` account.
+    pub operator_user_id: u32,
+    pub send_interval: u64,
+    pub confirmations: usize,
+    pub web3_url: String,
+    #[serde(default)]
+    pub rpc: RpcSettings,
+    /// Plaintext keystore credentials of older configs, superseded by `signer`.
+    #[serde(default)]
+    pub keystore: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(default)]
+    pub signer: Option<SignerSettings>,
+    pub chain_id: u64,
+    #[serde(default)]
+    pub fee: FeeSettings,
+    pub assets: HashMap<String, AssetSettings>,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct AssetSettings {
+    /// ERC20 token contract, native ETH is paid when absent.
+    pub token_address: Option<String>,
+    pub decimals: u32,
+}
+
+impl Settings {
+    /// Converts `self.send_interval` into `Duration`.
+    pub fn send_interval(&self) -> Duration {
+        Duration::from_millis(self.send_interval)
+    }
+}
@@ -0,0 +1,10 @@
+pub mod config;
+pub mod storage;
+pub mod withdraw_proposer;
+pub mod withdraw_sender;
+
+pub use config::Settings;
+pub use withdraw_proposer::WithdrawProposer;
+pub use withdraw_sender::WithdrawSender;
+
+mod msg;
@@ -0,0 +1,14 @@
+use crate::mq::messages::WrappedMessage;
+
+const MSG_TYPE_USERS: &str = "registeruser";
+const MSG_TYPE_TRANSFER: &str = "transfer";
+
+pub const MSG_CONSUMER_GROUP: &str = "tele_out_msg_consumer";
+
+pub fn decode(msg_type: &str, msg_payload: &str) -> Result<Option<WrappedMessage>, anyhow::Error> {
+    Ok(Some(match msg_type {
+        MSG_TYPE_USERS => WrappedMessage::User(serde_json::from_str(msg_payload)?),
+        MSG_TYPE_TRANSFER => WrappedMessage::Transfer(serde_json::from_str(msg_payload)?),
+        _ => return Ok(None),
+    }))
+}
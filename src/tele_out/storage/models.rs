@@ -0,0 +1,35 @@
+use crate::storage::{DecimalDbType, TimestampDbType};
+use serde::Serialize;
+
+pub mod tablenames {
+    pub const TELE_OUT_USER: &str = "tele_out_user";
+    pub const WITHDRAW_TX: &str = "withdraw_tx";
+}
+
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Serialize)]
+#[sqlx(type_name = "withdraw_status", rename_all = "snake_case")]
+pub enum WithdrawStatus {
+    Received,
+    Paying,
+    Paid,
+    Confirmed,
+    Failed,
+}
+
+#[derive(sqlx::FromRow, Debug, Clone, Serialize)]
+pub struct WithdrawTx {
+    pub id: i32,
+    pub from_user: i32,
+    pub asset: String,
+    pub amount: DecimalDbType,
+    pub l1_address: Option<String>,
+    pub status: WithdrawStatus,
+    /// Set once the transaction is signed, before it is broadcast.
+    pub l1_tx_hash: Option<String>,
+    pub nonce: Option<i64>,
+    #[serde(skip)]
+    pub raw_tx: Option<Vec<u8>>,
+    pub last_error: Option<String>,
+    pub created_time: TimestampDbType,
+    pub updated_time: TimestampDbType,
+}
@@ -0,0 +1,120 @@
+use super::msg;
+use crate::mq::messages::{TransferMessage, UserMessage, WrappedMessage};
+use crate::mq::{load_msgs_from_mq, PositionedMessage, MSG_CHANNEL_SIZE};
+use crate::storage::PoolType;
+use crate::tele_out::storage::models;
+use crate::tele_out::Settings;
+
+#[derive(Debug)]
+pub struct WithdrawProposer {
+    brokers: String,
+    connpool: PoolType,
+    operator_user_id: u32,
+}
+
+impl WithdrawProposer {
+    pub fn from_config_with_pool(config: &Settings, connpool: PoolType) -> Self {
+        Self {
+            brokers: config.brokers.to_owned(),
+            connpool,
+            operator_user_id: config.operator_user_id,
+        }
+    }
+
+    pub async fn run(&self) {
+        let (msg_sender, mut msg_receiver) = tokio::sync::mpsc::channel(MSG_CHANNEL_SIZE);
+        let loader_thread = load_msgs_from_mq(&self.brokers, msg::MSG_CONSUMER_GROUP, msg::decode, msg_sender);
+
+        while let Some(PositionedMessage {
+            partition,
+            offset,
+            message,
+        }) = msg_receiver.recv().await
+        {
+            let res = match message {
+                WrappedMessage::User(user) => self.save_user(&user).await,
+                WrappedMessage::Transfer(transfer) if is_withdraw(&transfer, self.operator_user_id) => {
+                    self.propose_withdraw(partition, offset, &transfer).await
+                }
+                WrappedMessage::Transfer(_) | WrappedMessage::Balance(_) => Ok(()),
+            };
+            if let Err(e) = res {
+                log::error!("handle message at partition {}, offset {}, error: {:?}", partition, offset, e);
+            }
+        }
+
+        loader_thread.map(|h| h.join().expect("loader thread failed"));
+    }
+
+    async fn save_user(&self, user: &UserMessage) -> Result<(), anyhow::Error> {
+        let stmt = format!(
+            "insert into {} (user_id, l1_address, l2_pubkey) values ($1, $2, $3)
+            on conflict (user_id) do update set l1_address = excluded.l1_address, l2_pubkey = excluded.l2_pubkey",
+            models::tablenames::TELE_OUT_USER
+        );
+        sqlx::query(&stmt)
+            .bind(user.user_id)
+            .bind(&user.l1_address)
+            .bind(&user.l2_pubkey)
+            .execute(&self.connpool)
+            .await?;
+        Ok(())
+    }
+
+    async fn propose_withdraw(&self, partition: i32, offset: i64, transfer: &TransferMessage) -> Result<(), anyhow::Error> {
+        // a redelivered message hits the (mq_partition, mq_offset) constraint and is skipped
+        let stmt = format!(
+            "insert into {} (mq_partition, mq_offset, from_user, asset, amount, l1_address)
+            values ($1, $2, $3, $4, $5, (select l1_address from {} where user_id = $3))
+            on conflict (mq_partition, mq_offset) do nothing",
+            models::tablenames::WITHDRAW_TX,
+            models::tablenames::TELE_OUT_USER
+        );
+        let res = sqlx::query(&stmt)
+            .bind(partition)
+            .bind(offset)
+            .bind(transfer.user_from as i32)
+            .bind(&transfer.asset)
+            .bind(transfer.amount)
+            .execute(&self.connpool)
+            .await?;
+
+        if res.rows_affected() > 0 {
+            log::info!(
+                "withdraw of ({:?}) amount of asset ({:?}) from user {:?} received",
+                transfer.amount,
+                transfer.asset,
+                transfer.user_from
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Whether `transfer` withdraws to L1, which users do by transferring to the operator.
+fn is_withdraw(transfer: &TransferMessage, operator_user_id: u32) -> bool {
+    transfer.user_to == operator_user_id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::DecimalDbType;
+
+    fn transfer(user_from: u32, user_to: u32) -> TransferMessage {
+        TransferMessage {
+            time: 0.0,
+            user_from,
+            user_to,
+            asset: "ETH".to_string(),
+            amount: DecimalDbType::new(15, 1),
+        }
+    }
+
+    #[test]
+    fn only_transfers_to_the_operator_are_withdrawals() {
+        assert!(is_withdraw(&transfer(5, 1), 1));
+        assert!(!is_withdraw(&transfer(1, 5), 1));
+        assert!(!is_withdraw(&transfer(5, 6), 1));
+    }
+}
@@ -0,0 +1,508 @@
+use crate::l1::transport;
+use crate::l1::{units, FeeEstimator, L1Provider, L1Signer, SignerSettings};
+use crate::storage::PoolType;
+use crate::tele_out::config::AssetSettings;
+use crate::tele_out::{storage::models, Settings};
+use anyhow::anyhow;
+use ethers::abi::{parse_abi, Abi};
+use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use std::collections::HashMap;
+use std::time::Duration;
+
+#[derive(Debug)]
+pub struct WithdrawSender {
+    connpool: PoolType,
+    send_interval: Duration,
+    client: L1Provider,
+    signer: L1Signer,
+    account: Address,
+    erc20_abi: Abi,
+    assets: HashMap<String, AssetSettings>,
+    confirmations: usize,
+    fee_estimator: FeeEstimator,
+}
+
+impl WithdrawSender {
+    pub async fn from_config_with_pool(config: &Settings, connpool: PoolType) -> Result<Self, anyhow::Error> {
+        let client = transport::provider(&config.web3_url, &config.rpc).await?;
+        let signer = SignerSettings::or_keystore(&config.signer, &config.keystore, &config.password)?;
+        let signer = L1Signer::from_config(&signer, config.chain_id).await?;
+        let account = signer.address();
+
+        Ok(Self {
+            connpool,
+            send_interval: config.send_interval(),
+            client,
+            signer,
+            account,
+            erc20_abi: parse_abi(&["function transfer(address to, uint256 amount) external returns (bool)"])?,
+            assets: config.assets.clone(),
+            confirmations: config.confirmations,
+            fee_estimator: FeeEstimator::from_config(&config.fee),
+        })
+    }
+
+    pub async fn run(&self) {
+        if let Err(e) = self.recover().await {
+            log::error!("recover unfinished withdrawals: {:?}", e);
+        }
+
+        let mut timer = tokio::time::interval(self.send_interval);
+        loop {
+            timer.tick().await;
+            log::debug!("ticktock!");
+
+            if let Err(e) = self.run_inner().await {
+                log::error!("{}", e);
+            };
+        }
+    }
+
+    /// Picks up withdrawals left behind by a previous run.
+    async fn recover(&self) -> Result<(), anyhow::Error> {
+        // the hash is stored before broadcasting, so one without it has never been sent
+        let stmt = format!(
+            "update {} set status = $1 where status = $2 and l1_tx_hash is null",
+            models::tablenames::WITHDRAW_TX
+        );
+        sqlx::query(&stmt)
+            .bind(models::WithdrawStatus::Received)
+            .bind(models::WithdrawStatus::Paying)
+            .execute(&self.connpool)
+            .await?;
+
+        let query = format!(
+            "select * from {} where status in ($1, $2) order by id",
+            models::tablenames::WITHDRAW_TX
+        );
+        let sent: Vec<models::WithdrawTx> = sqlx::query_as(&query)
+            .bind(models::WithdrawStatus::Paying)
+            .bind(models::WithdrawStatus::Paid)
+            .fetch_all(&self.connpool)
+            .await?;
+        for withdraw in sent {
+            self.confirm(&withdraw).await;
+        }
+
+        Ok(())
+    }
+
+    async fn run_inner(&self) -> Result<(), anyhow::Error> {
+        let withdraw = self
+            .claim_one_withdraw()
+            .await
+            .map_err(|e| anyhow!("claim_one_withdraw: {:?}", e))?;
+        let mut withdraw = match withdraw {
+            Some(w) => w,
+            None => return Ok(()),
+        };
+
+        let payment = match payment(&self.assets, &withdraw) {
+            Ok(payment) => payment,
+            Err(e) => {
+                self.mark_failed(withdraw.id, &e.to_string()).await?;
+                return Err(anyhow!("pay withdraw {}: {:?}", withdraw.id, e));
+            }
+        };
+        let (tx, raw_tx) = match self.sign(&payment).await {
+            Ok(signed) => signed,
+            Err(e) => {
+                // nothing has been broadcast, it is picked up again on the next tick
+                self.release(withdraw.id, &e.to_string()).await?;
+                return Err(anyhow!("pay withdraw {}: {:?}", withdraw.id, e));
+            }
+        };
+        let tx_hash = H256::from(ethers::utils::keccak256(&raw_tx));
+        let nonce = tx.nonce().map(|nonce| nonce.as_u64() as i64);
+        self.mark_signed(withdraw.id, tx_hash, nonce, &raw_tx).await?;
+        withdraw.l1_tx_hash = Some(format!("{:#x}", tx_hash));
+        withdraw.nonce = nonce;
+        withdraw.raw_tx = Some(raw_tx.to_vec());
+
+        match self.client.send_raw_transaction(raw_tx).await {
+            Ok(_) => {
+                self.mark_paid(withdraw.id).await?;
+                log::info!(
+                    "({:?}) amount of asset ({:?}) paid to {:?}, tx: {:#x}",
+                    withdraw.amount,
+                    withdraw.asset,
+                    withdraw.l1_address,
+                    tx_hash
+                );
+            }
+            // it may have reached a node all the same, `confirm` finds out
+            Err(e) => log::warn!("broadcast tx {:#x} of withdraw {}: {:?}", tx_hash, withdraw.id, e),
+        }
+
+        self.confirm(&withdraw).await;
+        Ok(())
+    }
+
+    async fn claim_one_withdraw(&self) -> Result<Option<models::WithdrawTx>, anyhow::Error> {
+        let mut tx = self.connpool.begin().await?;
+
+        let query = format!(
+            "select * from {} where status = $1 order by id limit 1 for update",
+            models::tablenames::WITHDRAW_TX
+        );
+        let fetch_res = sqlx::query_as::<_, models::WithdrawTx>(&query)
+            .bind(models::WithdrawStatus::Received)
+            .fetch_optional(&mut tx)
+            .await?;
+
+        if let Some(ref w) = fetch_res {
+            let stmt = format!("update {} set status = $1 where id = $2", models::tablenames::WITHDRAW_TX);
+            sqlx::query(&stmt)
+                .bind(models::WithdrawStatus::Paying)
+                .bind(w.id)
+                .execute(&mut tx)
+                .await?;
+        };
+
+        tx.commit().await?;
+        Ok(fetch_res)
+    }
+
+    /// Builds and signs the transaction of `payment`, with fees and nonce.
+    async fn sign(&self, payment: &Payment) -> Result<(TypedTransaction, Bytes), anyhow::Error> {
+        let tx: TypedTransaction = match payment.token {
+            None => Eip1559TransactionRequest::new()
+                .from(self.account)
+                .to(payment.to)
+                .value(payment.amount)
+                .into(),
+            Some(token) => {
+                let token = Contract::new(token, self.erc20_abi.clone(), self.client.clone());
+                token
+                    .method::<_, bool>("transfer", (payment.to, payment.amount))?
+                    .from(self.account)
+                    .tx
+            }
+        };
+        let mut tx = self.fee_estimator.apply(&self.client, tx).await?;
+        let nonce = self
+            .client
+            .get_transaction_count(self.account, Some(BlockNumber::Pending.into()))
+            .await?;
+        tx.set_nonce(nonce);
+        self.client.fill_transaction(&mut tx, None).await?;
+
+        let raw_tx = self.signer.sign(&tx).await?;
+        Ok((tx, raw_tx))
+    }
+
+    /// Follows the transaction of a signed withdrawal until it is confirmed or can no longer be mined.
+    async fn confirm(&self, withdraw: &models::WithdrawTx) {
+        loop {
+            match self.check(withdraw).await {
+                Ok(true) => return,
+                Ok(false) => {}
+                // most likely a node or database hiccup, checked again shortly
+                Err(e) => log::error!("confirm withdraw {}: {:?}", withdraw.id, e),
+            }
+            tokio::time::sleep(self.send_interval).await;
+        }
+    }
+
+    /// Looks at the transaction of `withdraw` once, returns whether it is settled.
+    async fn check(&self, withdraw: &models::WithdrawTx) -> Result<bool, anyhow::Error> {
+        let tx_hash = withdraw
+            .l1_tx_hash
+            .as_ref()
+            .ok_or_else(|| anyhow!("signed withdraw {} without l1_tx_hash", withdraw.id))?
+            .parse::<H256>()?;
+        let state = self.tx_state(withdraw, tx_hash).await?;
+        match state.outcome(self.confirmations as u64) {
+            Some(models::WithdrawStatus::Confirmed) => {
+                let stmt = format!("update {} set status = $1 where id = $2", models::tablenames::WITHDRAW_TX);
+                sqlx::query(&stmt)
+                    .bind(models::WithdrawStatus::Confirmed)
+                    .bind(withdraw.id)
+                    .execute(&self.connpool)
+                    .await?;
+                log::info!("withdraw {} confirmed in tx {:#x}", withdraw.id, tx_hash);
+                Ok(true)
+            }
+            Some(_) => {
+                let error = match state {
+                    TxState::Replaced => format!("nonce {:?} of tx {:#x} was used by another transaction", withdraw.nonce, tx_hash),
+                    _ => format!("tx {:#x} reverted", tx_hash),
+                };
+                self.mark_failed(withdraw.id, &error).await?;
+                log::error!("withdraw {} failed: {}", withdraw.id, error);
+                Ok(true)
+            }
+            None => {
+                if state == TxState::Pending {
+                    self.rebroadcast_if_unknown(withdraw, tx_hash).await?;
+                }
+                Ok(false)
+            }
+        }
+    }
+
+    async fn tx_state(&self, withdraw: &models::WithdrawTx, tx_hash: H256) -> Result<TxState, anyhow::Error> {
+        let head = self.client.get_block_number().await?.as_u64();
+        if let Some(receipt) = self.client.get_transaction_receipt(tx_hash).await? {
+            return TxState::mined(tx_hash, &receipt, head);
+        }
+        let nonce = match withdraw.nonce {
+            Some(nonce) => U256::from(nonce),
+            None => return Ok(TxState::Pending),
+        };
+        // only a nonce used `confirmations` deep counts, lagging or forked nodes must not fail a withdrawal
+        let settled = BlockNumber::Number(head.saturating_sub(self.confirmations as u64).into());
+        if self.client.get_transaction_count(self.account, Some(settled.into())).await? <= nonce {
+            return Ok(TxState::Pending);
+        }
+        // it may have been mined meanwhile
+        match self.client.get_transaction_receipt(tx_hash).await? {
+            Some(receipt) => TxState::mined(tx_hash, &receipt, head),
+            None => Ok(TxState::Replaced),
+        }
+    }
+
+    /// Broadcasts the signed transaction of `withdraw` again when no node knows it, its broadcast
+    /// may have failed or it may have fallen out of the mempools.
+    async fn rebroadcast_if_unknown(&self, withdraw: &models::WithdrawTx, tx_hash: H256) -> Result<(), anyhow::Error> {
+        let raw_tx = match &withdraw.raw_tx {
+            Some(raw_tx) => raw_tx,
+            None => return Ok(()),
+        };
+        if self.client.get_transaction(tx_hash).await?.is_some() {
+            return Ok(());
+        }
+        log::warn!("tx {:#x} of withdraw {} is unknown, broadcasting it again", tx_hash, withdraw.id);
+        if let Err(e) = self.client.send_raw_transaction(raw_tx.clone().into()).await {
+            log::warn!("rebroadcast tx {:#x}: {:?}", tx_hash, e);
+        }
+        Ok(())
+    }
+
+    /// Puts a withdrawal whose transaction was never signed back up for payment.
+    async fn release(&self, id: i32, error: &str) -> Result<(), anyhow::Error> {
+        let stmt = format!(
+            "update {} set status = $1, last_error = $2 where id = $3",
+            models::tablenames::WITHDRAW_TX
+        );
+        sqlx::query(&stmt)
+            .bind(models::WithdrawStatus::Received)
+            .bind(error)
+            .bind(id)
+            .execute(&self.connpool)
+            .await?;
+        Ok(())
+    }
+
+    /// Records the signed transaction before it is broadcast, so that a restart can look for it on-chain.
+    async fn mark_signed(&self, id: i32, tx_hash: H256, nonce: Option<i64>, raw_tx: &[u8]) -> Result<(), anyhow::Error> {
+        let stmt = format!(
+            "update {} set l1_tx_hash = $1, nonce = $2, raw_tx = $3 where id = $4",
+            models::tablenames::WITHDRAW_TX
+        );
+        sqlx::query(&stmt)
+            .bind(format!("{:#x}", tx_hash))
+            .bind(nonce)
+            .bind(raw_tx)
+            .bind(id)
+            .execute(&self.connpool)
+            .await?;
+        Ok(())
+    }
+
+    async fn mark_paid(&self, id: i32) -> Result<(), anyhow::Error> {
+        let stmt = format!("update {} set status = $1 where id = $2", models::tablenames::WITHDRAW_TX);
+        sqlx::query(&stmt)
+            .bind(models::WithdrawStatus::Paid)
+            .bind(id)
+            .execute(&self.connpool)
+            .await?;
+        Ok(())
+    }
+
+    async fn mark_failed(&self, id: i32, error: &str) -> Result<(), anyhow::Error> {
+        let stmt = format!(
+            "update {} set status = $1, last_error = $2 where id = $3",
+            models::tablenames::WITHDRAW_TX
+        );
+        sqlx::query(&stmt)
+            .bind(models::WithdrawStatus::Failed)
+            .bind(error)
+            .bind(id)
+            .execute(&self.connpool)
+            .await?;
+        Ok(())
+    }
+}
+
+/// A withdrawal as paid on L1.
+#[derive(Debug, PartialEq)]
+struct Payment {
+    to: Address,
+    /// ERC20 token contract, `None` for native ETH.
+    token: Option<Address>,
+    /// In the asset's base units.
+    amount: U256,
+}
+
+/// Works out what `withdraw` pays, an error here does not go away by retrying.
+fn payment(assets: &HashMap<String, AssetSettings>, withdraw: &models::WithdrawTx) -> Result<Payment, anyhow::Error> {
+    let to = withdraw
+        .l1_address
+        .as_ref()
+        .ok_or_else(|| anyhow!("unknown l1_address of user {}", withdraw.from_user))?
+        .parse::<Address>()?;
+    let asset = assets
+        .get(&withdraw.asset)
+        .ok_or_else(|| anyhow!("asset {} is not supported", withdraw.asset))?;
+    Ok(Payment {
+        to,
+        token: asset.token_address.as_ref().map(|token| token.parse::<Address>()).transpose()?,
+        amount: units::to_base_units(withdraw.amount, asset.decimals)?,
+    })
+}
+
+/// Where the transaction of a withdrawal stands on-chain.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TxState {
+    Mined {
+        succeeded: bool,
+        confirmations: u64,
+    },
+    /// Not mined yet and its nonce is still free.
+    Pending,
+    /// Not mined and its nonce used by another transaction, so it never will be.
+    Replaced,
+}
+
+impl TxState {
+    fn mined(tx_hash: H256, receipt: &TransactionReceipt, head: u64) -> Result<Self, anyhow::Error> {
+        let mined_at = receipt
+            .block_number
+            .ok_or_else(|| anyhow!("receipt of tx {:#x} has no block number", tx_hash))?
+            .as_u64();
+        Ok(TxState::Mined {
+            succeeded: receipt.status == Some(1u64.into()),
+            confirmations: (head + 1).saturating_sub(mined_at),
+        })
+    }
+
+    /// The status a withdrawal settles on, `None` while it has to be checked again.
+    fn outcome(self, confirmations: u64) -> Option<models::WithdrawStatus> {
+        match self {
+            TxState::Mined { confirmations: got, .. } if got < confirmations => None,
+            TxState::Mined { succeeded: true, .. } => Some(models::WithdrawStatus::Confirmed),
+            TxState::Mined { succeeded: false, .. } | TxState::Replaced => Some(models::WithdrawStatus::Failed),
+            TxState::Pending => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::DecimalDbType;
+    use std::str::FromStr;
+
+    const USER: &str = "0x00000000000000000000000000000000000000aa";
+    const USDT: &str = "0x00000000000000000000000000000000000000bb";
+
+    fn assets() -> HashMap<String, AssetSettings> {
+        let mut assets = HashMap::new();
+        assets.insert(
+            "ETH".to_string(),
+            AssetSettings {
+                token_address: None,
+                decimals: 18,
+            },
+        );
+        assets.insert(
+            "USDT".to_string(),
+            AssetSettings {
+                token_address: Some(USDT.to_string()),
+                decimals: 6,
+            },
+        );
+        assets
+    }
+
+    fn withdraw(asset: &str, amount: &str, l1_address: Option<&str>) -> models::WithdrawTx {
+        models::WithdrawTx {
+            id: 1,
+            from_user: 5,
+            asset: asset.to_string(),
+            amount: DecimalDbType::from_str(amount).unwrap(),
+            l1_address: l1_address.map(str::to_string),
+            status: models::WithdrawStatus::Paying,
+            l1_tx_hash: None,
+            nonce: None,
+            raw_tx: None,
+            last_error: None,
+            created_time: chrono::NaiveDateTime::from_timestamp(0, 0),
+            updated_time: chrono::NaiveDateTime::from_timestamp(0, 0),
+        }
+    }
+
+    #[test]
+    fn payment_in_base_units() {
+        let eth = payment(&assets(), &withdraw("ETH", "1.5", Some(USER))).unwrap();
+        assert_eq!(
+            eth,
+            Payment {
+                to: USER.parse().unwrap(),
+                token: None,
+                amount: U256::from(15) * U256::exp10(17),
+            }
+        );
+
+        let usdt = payment(&assets(), &withdraw("USDT", "2.000001", Some(USER))).unwrap();
+        assert_eq!(usdt.token, Some(USDT.parse().unwrap()));
+        assert_eq!(usdt.amount, U256::from(2_000_001));
+    }
+
+    #[test]
+    fn payment_rejects_what_cannot_be_paid() {
+        assert!(payment(&assets(), &withdraw("USDT", "0.0000001", Some(USER))).is_err());
+        assert!(payment(&assets(), &withdraw("DOGE", "1", Some(USER))).is_err());
+        assert!(payment(&assets(), &withdraw("ETH", "1", None)).is_err());
+    }
+
+    #[test]
+    fn mined_counts_confirmations_from_its_block() {
+        let receipt = TransactionReceipt {
+            block_number: Some(10u64.into()),
+            status: Some(1u64.into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            TxState::mined(H256::zero(), &receipt, 12).unwrap(),
+            TxState::Mined {
+                succeeded: true,
+                confirmations: 3
+            }
+        );
+        assert_eq!(
+            TxState::mined(H256::zero(), &receipt, 9).unwrap(),
+            TxState::Mined {
+                succeeded: true,
+                confirmations: 0
+            }
+        );
+    }
+
+    #[test]
+    fn outcome_waits_for_confirmations() {
+        let mined = |succeeded, confirmations| TxState::Mined { succeeded, confirmations };
+        assert_eq!(mined(true, 2).outcome(3), None);
+        assert_eq!(mined(false, 2).outcome(3), None);
+        assert_eq!(mined(true, 3).outcome(3), Some(models::WithdrawStatus::Confirmed));
+        assert_eq!(mined(false, 3).outcome(3), Some(models::WithdrawStatus::Failed));
+    }
+
+    #[test]
+    fn outcome_fails_only_a_replaced_tx() {
+        assert_eq!(TxState::Pending.outcome(3), None);
+        assert_eq!(TxState::Replaced.outcome(3), Some(models::WithdrawStatus::Failed));
+    }
+}